@@ -22,6 +22,6 @@ touch res/day${day}/sample.txt
 touch res/day${day}/input.txt
 
 # Change latest day.
-echo "Files created, please adjust latest day in src/solutions/mod.rs."
+echo "Files created, please adjust latest day and the day list in src/solutions/mod.rs."
 
 set +ex
@@ -2,5 +2,16 @@ mod solutions;
 mod utils;
 
 fn main() {
-    solutions::run_latest();
+    // With no arguments, just run the day being worked on.
+    let args = std::env::args().skip(1).collect::<Vec<String>>();
+    if args.is_empty() {
+        solutions::run_latest();
+        return;
+    }
+
+    if let Err(error) = solutions::run_from_args(&args) {
+        eprintln!("{}", error);
+        eprintln!("Usage: advent_of_code [all | latest | <day> [--option value]...]");
+        std::process::exit(1);
+    }
 }
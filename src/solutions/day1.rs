@@ -1,48 +1,8 @@
 use crate::utils;
-
-use std::collections::VecDeque;
+use crate::utils::window::{Sum, WindowExt};
 
 pub struct Day1;
 
-struct SumQueue {
-    vec: VecDeque<i64>,
-    sum: i64,
-    testable: bool,
-}
-
-impl SumQueue {
-    pub fn new(size: usize) -> Self {
-        SumQueue {
-            vec: VecDeque::with_capacity(size),
-            sum: 0,
-            testable: false,
-        }
-    }
-
-    pub fn sum(&self) -> i64 {
-        self.sum
-    }
-
-    pub fn testable(&self) -> bool {
-        self.testable
-    }
-
-    pub fn at_capacity(&self) -> bool {
-        self.vec.len() == self.vec.capacity()
-    }
-
-    pub fn push(&mut self, num: i64) -> &mut Self {
-        if self.at_capacity() {
-            self.sum -= self.vec.pop_front().unwrap();
-            self.testable = true;
-        }
-
-        self.vec.push_back(num);
-        self.sum += num;
-        self
-    }
-}
-
 impl utils::Solution for Day1 {
     fn day_number() -> i32 {
         1
@@ -68,24 +28,24 @@ impl utils::Solution for Day1 {
     }
 
     fn solution_impl_2(data: Vec<String>) -> Option<i64> {
-        let mut prev: Option<i64> = None;
-        let mut count = 0i64;
-        let mut queue = SumQueue::new(3);
-        for line in data {
-            let parsed: i64 = line.parse().unwrap();
-            let new_sum = queue.push(parsed).sum();
-            if prev.is_none() {
-                prev = new_sum.into();
-                continue;
-            }
+        let sums = data
+            .iter()
+            .map(|line| line.parse::<i64>().unwrap())
+            .window_aggregate::<Sum<i64>>(3)
+            .collect::<Vec<i64>>();
 
-            if queue.testable() && new_sum > prev.unwrap() {
-                count += 1;
-            }
+        Some(sums.windows(2).filter(|pair| pair[1] > pair[0]).count() as i64)
+    }
+}
 
-            prev = new_sum.into();
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::Solution;
 
-        Some(count)
+    #[test]
+    fn sample() {
+        assert_eq!(Day1::solution_impl_1(Day1::sample_data()), Some(7));
+        assert_eq!(Day1::solution_impl_2(Day1::sample_data()), Some(5));
     }
 }
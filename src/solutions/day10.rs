@@ -1,74 +1,464 @@
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet};
 
-use crate::utils::Solution;
+use crate::utils::checked::{checked_sum, CheckedArith, OverflowError};
+use crate::utils::cli::Args;
+use crate::utils::{Colour, Solution};
 
 pub struct Day10;
 
-fn value_of(c: Option<char>) -> i64 {
-    match c {
-        Some(')') => 3,
-        Some(']') => 57,
-        Some('}') => 1197,
-        Some('>') => 25137,
-        _ => 0,
-    }
+/// What to do with characters that are neither an opener nor a closer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum UnknownChars {
+    Skip,
+    Reject,
 }
 
-fn part2_score(s: &str) -> i64 {
-    let scores = HashMap::from([('(', 1), ('[', 2), ('{', 3), ('<', 4)]);
-    let mut score: i64 = 0;
-    for c in s.chars() {
-        score *= 5;
-        score += scores.get(&c).unwrap();
-    }
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Analysis {
+    /// Every opener was closed by its partner.
+    Valid,
+    /// A closer didn't match the innermost open bracket. `expected` is the closer that was
+    /// wanted, if anything was open at all, and `opened_at` is where that bracket was opened.
+    Corrupted {
+        position: usize,
+        expected: Option<char>,
+        found: char,
+        opened_at: Option<usize>,
+    },
+    /// The line ended with brackets still open; `completion` closes them all.
+    Incomplete { completion: String },
+    /// A character outside the configured pairs, with `UnknownChars::Reject`.
+    Unknown { position: usize, found: char },
+}
 
-    score
+#[derive(Clone, Debug)]
+struct BracketAnalyser {
+    // Mapping of {opener => closer}.
+    closers: HashMap<char, char>,
+    // Mapping of {closer => opener}.
+    openers: HashMap<char, char>,
+    unknown: UnknownChars,
 }
 
-fn first_unmatched(s: &str) -> Option<char> {
-    let pairs = HashMap::from([(')', '('), (']', '['), ('}', '{'), ('>', '<')]);
-    let mut stack: VecDeque<char> = VecDeque::new();
-    for c in s.chars() {
-        match c {
-            '(' | '[' | '{' | '<' => {
-                stack.push_back(c);
-            }
-            ')' | ']' | '}' | '>' => {
-                let back = stack.pop_back();
-                if back.is_none() || pairs.get(&c).unwrap() != &back.unwrap() {
-                    return Some(c);
+impl BracketAnalyser {
+    fn new(pairs: &[(char, char)], unknown: UnknownChars) -> Self {
+        BracketAnalyser {
+            closers: pairs.iter().cloned().collect(),
+            openers: pairs.iter().map(|&(open, close)| (close, open)).collect(),
+            unknown,
+        }
+    }
+
+    fn standard() -> Self {
+        BracketAnalyser::new(
+            &[('(', ')'), ('[', ']'), ('{', '}'), ('<', '>')],
+            UnknownChars::Reject,
+        )
+    }
+
+    /// Pairs given as consecutive opener/closer characters, e.g. "()[]".
+    fn from_spec(pairs: &str, unknown: UnknownChars) -> Result<Self, String> {
+        let chars = pairs.chars().collect::<Vec<char>>();
+        if chars.is_empty() || chars.len() % 2 != 0 {
+            return Err(format!(
+                "Bracket pairs \"{}\" must be opener/closer pairs",
+                pairs
+            ));
+        }
+
+        // Each character may only play one role, or analysis couldn't tell what it means.
+        let mut seen = HashSet::new();
+        if let Some(repeated) = chars.iter().find(|&&c| !seen.insert(c)) {
+            return Err(format!(
+                "Bracket pairs \"{}\" use '{}' more than once",
+                pairs, repeated
+            ));
+        }
+
+        let pairs = chars
+            .chunks(2)
+            .map(|pair| (pair[0], pair[1]))
+            .collect::<Vec<(char, char)>>();
+        Ok(BracketAnalyser::new(&pairs, unknown))
+    }
+
+    /// Positions are character offsets into `line`.
+    fn analyse(&self, line: &str) -> Analysis {
+        // (opener, position) for every bracket still open, innermost last.
+        let mut stack: Vec<(char, usize)> = vec![];
+        for (position, c) in line.chars().enumerate() {
+            if self.closers.contains_key(&c) {
+                stack.push((c, position));
+            } else if let Some(&opener) = self.openers.get(&c) {
+                match stack.pop() {
+                    Some((open, _)) if open == opener => (),
+                    innermost => {
+                        return Analysis::Corrupted {
+                            position,
+                            expected: innermost.map(|(open, _)| self.closers[&open]),
+                            found: c,
+                            opened_at: innermost.map(|(_, opened_at)| opened_at),
+                        }
+                    }
                 }
+            } else if self.unknown == UnknownChars::Reject {
+                return Analysis::Unknown { position, found: c };
+            }
+        }
+
+        if stack.is_empty() {
+            Analysis::Valid
+        } else {
+            Analysis::Incomplete {
+                completion: stack
+                    .iter()
+                    .rev()
+                    .map(|(open, _)| self.closers[open])
+                    .collect(),
             }
-            ch => panic!("Unsupported char '{}'", ch),
         }
     }
+}
+
+type CompletionScorer = Box<dyn Fn(&str) -> Result<i64, OverflowError>>;
 
-    None
+/// Points awarded for each kind of problem a line can have.
+struct Scoring {
+    // Mapping of {illegal closer => points}.
+    corruption: HashMap<char, i64>,
+    completion: CompletionScorer,
 }
 
-fn all_unmatched(s: &str) -> Option<String> {
-    let pairs = HashMap::from([(')', '('), (']', '['), ('}', '{'), ('>', '<')]);
-    let mut stack: VecDeque<char> = VecDeque::new();
-    for c in s.chars() {
-        match c {
-            '(' | '[' | '{' | '<' => {
-                stack.push_back(c);
-            }
-            ')' | ']' | '}' | '>' => {
-                let back = stack.pop_back();
-                if back.is_none() || pairs.get(&c).unwrap() != &back.unwrap() {
-                    return None;
+impl Scoring {
+    fn standard() -> Self {
+        Scoring {
+            corruption: HashMap::from([(')', 3), (']', 57), ('}', 1197), ('>', 25137)]),
+            completion: Scoring::positional(
+                HashMap::from([(')', 1), (']', 2), ('}', 3), ('>', 4)]),
+                5,
+            ),
+        }
+    }
+
+    /// Completion scoring that multiplies the running score by `multiplier` before adding each
+    /// character's points. Characters without points are worth nothing.
+    fn positional(points: HashMap<char, i64>, multiplier: i64) -> CompletionScorer {
+        Box::new(move |completion| {
+            completion.chars().try_fold(0i64, |score, c| {
+                score
+                    .try_mul(multiplier)?
+                    .try_add(*points.get(&c).unwrap_or(&0))
+            })
+        })
+    }
+
+    /// Reads a config with one setting per line, ignoring blank lines and `#` comments:
+    ///
+    /// corrupt ) 3
+    /// complete ) 1
+    /// multiplier 5
+    fn from_config(text: &str) -> Result<Self, String> {
+        let mut corruption = HashMap::new();
+        let mut completion = HashMap::new();
+        let mut multiplier = 5;
+        for (idx, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            let err = || format!("Invalid scoring on line {}: \"{}\"", idx + 1, line);
+            match line.split_whitespace().collect::<Vec<&str>>()[..] {
+                [] => (),
+                ["multiplier", value] => multiplier = value.parse().map_err(|_| err())?,
+                [kind @ ("corrupt" | "complete"), bracket, value] => {
+                    let mut chars = bracket.chars();
+                    let bracket = match (chars.next(), chars.next()) {
+                        (Some(bracket), None) => bracket,
+                        _ => return Err(err()),
+                    };
+                    let value = value.parse::<i64>().map_err(|_| err())?;
+                    match kind {
+                        "corrupt" => corruption.insert(bracket, value),
+                        _ => completion.insert(bracket, value),
+                    };
                 }
+                _ => return Err(err()),
+            }
+        }
+
+        Ok(Scoring {
+            corruption,
+            completion: Scoring::positional(completion, multiplier),
+        })
+    }
+
+    fn score(&self, analysis: &Analysis) -> Result<i64, OverflowError> {
+        match analysis {
+            Analysis::Corrupted { found, .. } => Ok(*self.corruption.get(found).unwrap_or(&0)),
+            Analysis::Incomplete { completion } => (self.completion)(completion),
+            Analysis::Valid | Analysis::Unknown { .. } => Ok(0),
+        }
+    }
+}
+
+struct LineScore {
+    analysis: Analysis,
+    score: i64,
+}
+
+struct ScoreReport {
+    lines: Vec<LineScore>,
+}
+
+impl ScoreReport {
+    fn new<'a>(
+        analyser: &BracketAnalyser,
+        scoring: &Scoring,
+        lines: impl IntoIterator<Item = &'a String>,
+    ) -> Result<Self, OverflowError> {
+        let lines = lines
+            .into_iter()
+            .map(|line| {
+                let analysis = analyser.analyse(line);
+                let score = scoring.score(&analysis)?;
+                Ok(LineScore { analysis, score })
+            })
+            .collect::<Result<Vec<LineScore>, OverflowError>>()?;
+
+        Ok(ScoreReport { lines })
+    }
+
+    fn unknown_char(&self) -> Option<char> {
+        self.lines.iter().find_map(|line| match line.analysis {
+            Analysis::Unknown { found, .. } => Some(found),
+            _ => None,
+        })
+    }
+
+    fn syntax_error_total(&self) -> Result<i64, OverflowError> {
+        checked_sum(
+            self.lines
+                .iter()
+                .filter(|line| matches!(line.analysis, Analysis::Corrupted { .. }))
+                .map(|line| line.score),
+        )
+    }
+
+    fn middle_completion_score(&self) -> Option<i64> {
+        let mut scores = self
+            .lines
+            .iter()
+            .filter(|line| matches!(line.analysis, Analysis::Incomplete { .. }))
+            .map(|line| line.score)
+            .collect::<Vec<i64>>();
+
+        scores.sort_unstable();
+        scores.get(scores.len() / 2).cloned()
+    }
+}
+
+fn describe(analysis: &Analysis) -> String {
+    match analysis {
+        Analysis::Valid => "valid".to_string(),
+        Analysis::Corrupted {
+            position,
+            expected: Some(expected),
+            found,
+            ..
+        } => format!(
+            "corrupted at {}: expected '{}', found '{}'",
+            position + 1,
+            expected,
+            found
+        ),
+        Analysis::Corrupted {
+            position, found, ..
+        } => format!("corrupted at {}: unopened '{}'", position + 1, found),
+        Analysis::Incomplete { completion } => format!("incomplete, needs {}", completion),
+        Analysis::Unknown { position, found } => {
+            format!("unknown character '{}' at {}", found, position + 1)
+        }
+    }
+}
+
+/// Compiler-style report for a line that isn't valid or merely incomplete, with a caret under
+/// the offending character and, for mismatches, a marker under the bracket it should close.
+///
+/// error: expected ']', found '}'
+///  --> line 3, column 13
+///   |
+/// 3 | {([(<{}[<>[]}>{[]{[(<()>
+///   |        -    ^ expected ']'
+///   |        |
+///   |        '[' opened here
+fn render_diagnostic(
+    line_number: usize,
+    line: &str,
+    analysis: &Analysis,
+    colour: bool,
+) -> Option<String> {
+    let paint = |text: String, colours: &[Colour]| {
+        if colour {
+            colours.iter().map(Colour::to_string).collect::<String>()
+                + &text
+                + Colour::Reset.to_string()
+        } else {
+            text
+        }
+    };
+
+    let (message, position, label, opener) = match analysis {
+        Analysis::Corrupted {
+            position,
+            expected: Some(expected),
+            found,
+            opened_at,
+        } => (
+            format!("expected '{}', found '{}'", expected, found),
+            *position,
+            format!("expected '{}'", expected),
+            *opened_at,
+        ),
+        Analysis::Corrupted {
+            position, found, ..
+        } => (
+            format!("unexpected '{}' with no bracket open", found),
+            *position,
+            "nothing to close".to_string(),
+            None,
+        ),
+        Analysis::Unknown { position, found } => (
+            format!("unknown character '{}'", found),
+            *position,
+            "not a bracket".to_string(),
+            None,
+        ),
+        Analysis::Valid | Analysis::Incomplete { .. } => return None,
+    };
+
+    let number = line_number.to_string();
+    let gutter = " ".repeat(number.len());
+    let bar = paint(format!("{} |", gutter), &[Colour::Blue, Colour::Bold]);
+    let caret = paint(format!("^ {}", label), &[Colour::Red, Colour::Bold]);
+
+    let mut res = vec![
+        paint(format!("error: {}", message), &[Colour::Red, Colour::Bold]),
+        format!(
+            "{}{} line {}, column {}",
+            gutter,
+            paint("-->".to_string(), &[Colour::Blue, Colour::Bold]),
+            line_number,
+            position + 1
+        ),
+        bar.clone(),
+        format!(
+            "{} {}",
+            paint(format!("{} |", number), &[Colour::Blue, Colour::Bold]),
+            line
+        ),
+    ];
+
+    match opener {
+        Some(opened_at) => {
+            let opened = line.chars().nth(opened_at).unwrap();
+            let indent = " ".repeat(opened_at);
+            let gap = " ".repeat(position - opened_at - 1);
+            res.push(format!(
+                "{} {}{}{}{}",
+                bar,
+                indent,
+                paint("-".to_string(), &[Colour::Blue, Colour::Bold]),
+                gap,
+                caret
+            ));
+            res.push(format!(
+                "{} {}{}",
+                bar,
+                indent,
+                paint("|".to_string(), &[Colour::Blue, Colour::Bold])
+            ));
+            res.push(format!(
+                "{} {}{}",
+                bar,
+                indent,
+                paint(
+                    format!("'{}' opened here", opened),
+                    &[Colour::Blue, Colour::Bold]
+                )
+            ));
+        }
+        None => res.push(format!("{} {}{}", bar, " ".repeat(position), caret)),
+    }
+
+    Some(res.join("\n"))
+}
+
+pub fn run_cli(options: &[String]) -> Result<(), String> {
+    let args = Args::parse(
+        options,
+        &[
+            "pairs",
+            "unknown",
+            "data",
+            "scores",
+            "diagnostics",
+            "colour",
+        ],
+    )?;
+
+    let unknown = match args.get_or("unknown", "reject".to_string())?.as_str() {
+        "skip" => UnknownChars::Skip,
+        "reject" => UnknownChars::Reject,
+        other => return Err(format!("Unknown character policy \"{}\"", other)),
+    };
+    let analyser = match args.get::<String>("pairs")? {
+        Some(pairs) => BracketAnalyser::from_spec(&pairs, unknown)?,
+        None => BracketAnalyser {
+            unknown,
+            ..BracketAnalyser::standard()
+        },
+    };
+    let scoring = match args.get::<String>("scores")? {
+        Some(path) => Scoring::from_config(
+            &std::fs::read_to_string(&path)
+                .map_err(|error| format!("Failed to read {}: {}", path, error))?,
+        )?,
+        None => Scoring::standard(),
+    };
+
+    let data = match args.get_or("data", "input".to_string())?.as_str() {
+        "sample" => Day10::sample_data(),
+        "input" => Day10::input_data(),
+        other => return Err(format!("Unknown data set \"{}\"", other)),
+    };
+    let report = ScoreReport::new(&analyser, &scoring, &data).map_err(|error| error.to_string())?;
+    if args.get_or("diagnostics", false)? {
+        let colour = args.get_or("colour", false)?;
+        for (idx, (line, scored)) in data.iter().zip(&report.lines).enumerate() {
+            if let Some(diagnostic) = render_diagnostic(idx + 1, line, &scored.analysis, colour) {
+                println!("{}\n", diagnostic);
             }
-            ch => panic!("Unsupported char '{}'", ch),
         }
     }
 
-    if !stack.is_empty() {
-        Some(stack.iter().rev().collect::<String>())
-    } else {
-        None
+    for (idx, line) in report.lines.iter().enumerate() {
+        println!(
+            "{:>4}: {:>12}  {}",
+            idx + 1,
+            line.score,
+            describe(&line.analysis)
+        );
     }
+
+    println!(
+        "Syntax error total: {}",
+        report
+            .syntax_error_total()
+            .map_err(|error| error.to_string())?
+    );
+    match report.middle_completion_score() {
+        Some(score) => println!("Middle completion score: {}", score),
+        None => println!("No incomplete lines"),
+    }
+
+    Ok(())
 }
 
 impl Solution for Day10 {
@@ -77,24 +467,177 @@ impl Solution for Day10 {
     }
 
     fn solution_impl_1(data: Vec<String>) -> Option<i64> {
-        data.iter()
-            .map(String::as_str)
-            .map(first_unmatched)
-            .map(value_of)
-            .sum::<i64>()
-            .into()
+        let report =
+            ScoreReport::new(&BracketAnalyser::standard(), &Scoring::standard(), &data).ok()?;
+        if let Some(found) = report.unknown_char() {
+            panic!("Unsupported char '{}'", found);
+        }
+
+        report.syntax_error_total().ok()
     }
 
     fn solution_impl_2(data: Vec<String>) -> Option<i64> {
-        let mut scores = data
-            .iter()
-            .map(String::as_str)
-            .map(all_unmatched)
-            .filter(Option::is_some)
-            .map(|s| part2_score(&s.unwrap()))
-            .collect::<Vec<i64>>();
+        let report =
+            ScoreReport::new(&BracketAnalyser::standard(), &Scoring::standard(), &data).ok()?;
+        if let Some(found) = report.unknown_char() {
+            panic!("Unsupported char '{}'", found);
+        }
 
-        scores.sort_unstable();
-        scores[scores.len() / 2].into()
+        report.middle_completion_score()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_spec_rejects_reused_characters() {
+        for spec in ["()||", "((", "()[(", "()[)", "()(]", ""] {
+            assert!(
+                BracketAnalyser::from_spec(spec, UnknownChars::Reject).is_err(),
+                "{}",
+                spec
+            );
+        }
+    }
+
+    #[test]
+    fn custom_pairs() {
+        let analyser = BracketAnalyser::from_spec("()/\\", UnknownChars::Skip).unwrap();
+        assert_eq!(analyser.analyse("(/x\\)"), Analysis::Valid);
+        assert_eq!(
+            analyser.analyse("(/"),
+            Analysis::Incomplete {
+                completion: "\\)".to_string()
+            }
+        );
+        assert_eq!(
+            analyser.analyse("(\\"),
+            Analysis::Corrupted {
+                position: 1,
+                expected: Some(')'),
+                found: '\\',
+                opened_at: Some(0)
+            }
+        );
+    }
+
+    fn lines(text: &[&str]) -> Vec<String> {
+        text.iter().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn scoring_config() {
+        let scoring = Scoring::from_config(
+            "# Custom scores\n\
+             corrupt } 10\n\
+             complete ] 2   # brackets without points are worth nothing\n\
+             complete } 3\n\
+             \n\
+             multiplier 10\n",
+        )
+        .unwrap();
+        let data = lines(&["{([(<{}[<>[]}>{[]{[(<()>", "[({", "()", "(a", "(]"]);
+        let report = ScoreReport::new(&BracketAnalyser::standard(), &scoring, &data).unwrap();
+        assert_eq!(
+            report
+                .lines
+                .iter()
+                .map(|line| line.score)
+                .collect::<Vec<i64>>(),
+            vec![10, 302, 0, 0, 0]
+        );
+        assert_eq!(report.syntax_error_total(), Ok(10));
+        assert_eq!(report.middle_completion_score(), Some(302));
+        assert_eq!(report.unknown_char(), Some('a'));
+    }
+
+    #[test]
+    fn scoring_config_errors() {
+        for (config, line) in [
+            ("bonus ) 3", "bonus ) 3"),
+            ("corrupt )", "corrupt )"),
+            ("multiplier 5\ncorrupt )) 3", "corrupt )) 3"),
+            ("complete ) x", "complete ) x"),
+            (
+                "complete ) 99999999999999999999",
+                "complete ) 99999999999999999999",
+            ),
+            ("multiplier five # comment", "multiplier five"),
+        ] {
+            let number = config.lines().count();
+            assert_eq!(
+                Scoring::from_config(config).err(),
+                Some(format!("Invalid scoring on line {}: \"{}\"", number, line)),
+                "{}",
+                config
+            );
+        }
+    }
+
+    #[test]
+    fn score_overflow_is_an_error() {
+        let scoring = Scoring::from_config("complete ) 1\nmultiplier 1000000000").unwrap();
+        let data = lines(&["((", "(((((((("]);
+        let analyser = BracketAnalyser::standard();
+        assert!(ScoreReport::new(&analyser, &scoring, &data[..1]).is_ok());
+        assert!(ScoreReport::new(&analyser, &scoring, &data).is_err());
+    }
+
+    fn diagnostic(line_number: usize, line: &str) -> Option<String> {
+        let analysis = BracketAnalyser::standard().analyse(line);
+        render_diagnostic(line_number, line, &analysis, false)
+    }
+
+    #[test]
+    fn diagnostic_for_mismatch() {
+        assert_eq!(
+            diagnostic(3, "{([(<{}[<>[]}>{[]{[(<()>").unwrap(),
+            [
+                "error: expected ']', found '}'",
+                " --> line 3, column 13",
+                "  |",
+                "3 | {([(<{}[<>[]}>{[]{[(<()>",
+                "  |        -    ^ expected ']'",
+                "  |        |",
+                "  |        '[' opened here",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn diagnostic_for_nothing_open_and_unknown() {
+        assert_eq!(
+            diagnostic(12, "()]").unwrap(),
+            [
+                "error: unexpected ']' with no bracket open",
+                "  --> line 12, column 3",
+                "   |",
+                "12 | ()]",
+                "   |   ^ nothing to close",
+            ]
+            .join("\n")
+        );
+        assert_eq!(
+            diagnostic(1, "(a)").unwrap(),
+            [
+                "error: unknown character 'a'",
+                " --> line 1, column 2",
+                "  |",
+                "1 | (a)",
+                "  |  ^ not a bracket",
+            ]
+            .join("\n")
+        );
+        assert_eq!(diagnostic(1, "()"), None);
+        assert_eq!(diagnostic(1, "(("), None);
+    }
+
+    #[test]
+    fn sample() {
+        assert_eq!(Day10::solution_impl_1(Day10::sample_data()), Some(26397));
+        assert_eq!(Day10::solution_impl_2(Day10::sample_data()), Some(288957));
     }
 }
@@ -1,6 +1,7 @@
 use std::collections::HashSet;
 
-use crate::solutions::day9::Grid;
+use crate::utils::automaton::{Automaton, Simulation};
+use crate::utils::grid::Grid;
 use crate::utils::Solution;
 
 pub struct Day11;
@@ -45,11 +46,10 @@ impl Grid {
     }
 }
 
-trait Automata<T> {
-    fn step(&mut self) -> T;
-}
+impl Automaton for Grid {
+    // Number of octopuses that flashed.
+    type Stats = i64;
 
-impl Automata<i64> for Grid {
     fn step(&mut self) -> i64 {
         let mut overflowed: HashSet<(i64, i64)> = HashSet::new();
         for row in 0..self.data.len() as i64 {
@@ -79,24 +79,13 @@ impl Solution for Day11 {
     }
 
     fn solution_impl_1(data: Vec<String>) -> Option<i64> {
-        let mut grid = Grid::from_data(data);
-        let mut res: i64 = 0;
-        for _ in 0..100 {
-            res += grid.step();
-        }
-
-        res.into()
+        let mut simulation = Simulation::new(Grid::from_data(data));
+        simulation.run(100).iter().sum::<i64>().into()
     }
 
     fn solution_impl_2(data: Vec<String>) -> Option<i64> {
-        let mut grid = Grid::from_data(data);
-        for step in 0.. {
-            if grid.is_all_zeroes() {
-                return (step as i64).into();
-            }
-            grid.step();
-        }
-
-        unreachable!()
+        let mut simulation = Simulation::new(Grid::from_data(data));
+        simulation.run_until(Grid::is_all_zeroes);
+        (simulation.generation as i64).into()
     }
 }
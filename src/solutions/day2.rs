@@ -1,7 +1,12 @@
+use crate::utils::parse::{self, integer, key_value, literal, word};
 use crate::utils::{Solution, XY};
 
 pub struct Day2;
 
+fn parse_commands(data: &[String]) -> Vec<(&str, i64)> {
+    parse::parse_lines(key_value(word(), literal(" "), integer::<i64>()), data).unwrap()
+}
+
 impl Solution for Day2 {
     fn day_number() -> i32 {
         2
@@ -9,12 +14,12 @@ impl Solution for Day2 {
 
     fn solution_impl_1(data: Vec<String>) -> Option<i64> {
         let mut pos: XY<i64> = XY::new(0, 0);
-        for line in data {
-            match &line.split(" ").collect::<Vec<&str>>()[..] {
-                ["forward", val] => pos.x += val.parse::<i64>().unwrap(),
-                ["down", val] => pos.y += val.parse::<i64>().unwrap(),
-                ["up", val] => pos.y -= val.parse::<i64>().unwrap(),
-                _ => panic!("Unexpected command: {}", line),
+        for (command, val) in parse_commands(&data) {
+            match command {
+                "forward" => pos += XY::new(val, 0),
+                "down" => pos += XY::new(0, val),
+                "up" => pos -= XY::new(0, val),
+                _ => panic!("Unexpected command: {}", command),
             }
         }
 
@@ -24,16 +29,12 @@ impl Solution for Day2 {
     fn solution_impl_2(data: Vec<String>) -> Option<i64> {
         let mut pos: XY<i64> = XY::new(0, 0);
         let mut aim = 0i64;
-        for line in data {
-            match &line.split(" ").collect::<Vec<&str>>()[..] {
-                ["down", val] => aim += val.parse::<i64>().unwrap(),
-                ["up", val] => aim -= val.parse::<i64>().unwrap(),
-                ["forward", val] => {
-                    let parsed = val.parse::<i64>().unwrap();
-                    pos.x += parsed;
-                    pos.y += aim * parsed;
-                }
-                _ => panic!("Unexpected command: {}", line),
+        for (command, val) in parse_commands(&data) {
+            match command {
+                "down" => aim += val,
+                "up" => aim -= val,
+                "forward" => pos += XY::new(val, aim * val),
+                _ => panic!("Unexpected command: {}", command),
             }
         }
 
@@ -34,7 +34,7 @@ impl Solution for Day3 {
     }
 
     fn solution_impl_1(data: Vec<String>) -> Option<i64> {
-        assert!(data.len() > 0, "Input data has size 0, cannot proceed");
+        assert!(!data.is_empty(), "Input data has size 0, cannot proceed");
 
         let binary_length = data.iter().map(|s| s.len()).max().unwrap();
         let mut counts = vec![0; binary_length];
@@ -59,7 +59,7 @@ impl Solution for Day3 {
     fn solution_impl_2(data: Vec<String>) -> Option<i64> {
         // Oxygen: keep most common bit, 1 if equal.
         // CO2: keep least common bit, 0 if equal.
-        assert!(data.len() > 0, "Input data has size 0, cannot proceed");
+        assert!(!data.is_empty(), "Input data has size 0, cannot proceed");
 
         let binary_length = data.iter().map(|s| s.len()).max().unwrap();
         let numbers: Vec<i64> = data.iter().map(|s| to_i64(s)).collect();
@@ -1,12 +1,143 @@
 use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
-use std::iter::Iterator;
 
+use crate::utils::cli::Args;
+use crate::utils::parse;
 use crate::utils::Solution;
 use crate::utils::XY;
 
 pub struct Day4;
 
+/// A way to complete a board. Each rule expands to sets of cells, and a board wins as soon as
+/// every cell of any one set has been called.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum WinRule {
+    Rows,
+    Columns,
+    /// Both corner-to-corner diagonals. Only square boards have them.
+    Diagonals,
+    Corners,
+    FullCard,
+    /// Cells marked `true`, on a mask the same size as the board.
+    Pattern(Vec<Vec<bool>>),
+}
+
+impl WinRule {
+    /// Mask rows made of '#' for cells that must be called and '.' for the rest.
+    fn pattern(rows: &[&str]) -> Result<Self, String> {
+        rows.iter()
+            .map(|row| {
+                row.chars()
+                    .map(|c| match c {
+                        '#' => Ok(true),
+                        '.' => Ok(false),
+                        other => Err(format!("Unexpected '{}' in win pattern", other)),
+                    })
+                    .collect()
+            })
+            .collect::<Result<Vec<Vec<bool>>, String>>()
+            .and_then(|mask| {
+                // An empty set of cells would never be checked, so the rule could never win.
+                if mask.iter().flatten().any(|&cell| cell) {
+                    Ok(WinRule::Pattern(mask))
+                } else {
+                    Err("Win pattern needs at least one '#'".to_string())
+                }
+            })
+    }
+
+    /// Parses one of rows, columns, diagonals, corners, full or pattern:<rows separated by '/'>.
+    fn from_name(name: &str) -> Result<Self, String> {
+        match name {
+            "rows" => Ok(WinRule::Rows),
+            "columns" => Ok(WinRule::Columns),
+            "diagonals" => Ok(WinRule::Diagonals),
+            "corners" => Ok(WinRule::Corners),
+            "full" => Ok(WinRule::FullCard),
+            other => match other.strip_prefix("pattern:") {
+                Some(mask) => WinRule::pattern(&mask.split('/').collect::<Vec<&str>>()),
+                None => Err(format!("Unknown win rule \"{}\"", other)),
+            },
+        }
+    }
+
+    /// Every set of cells that completes this rule on a `rows` × `cols` board, or why the rule
+    /// can't be played on a board of that size.
+    fn lines(&self, rows: usize, cols: usize) -> Result<Vec<Vec<XY<usize>>>, String> {
+        match self {
+            WinRule::Rows => Ok((0..rows)
+                .map(|row| (0..cols).map(|col| XY::new(row, col)).collect())
+                .collect()),
+            WinRule::Columns => Ok((0..cols)
+                .map(|col| (0..rows).map(|row| XY::new(row, col)).collect())
+                .collect()),
+            WinRule::Diagonals if rows == cols => Ok(vec![
+                (0..rows).map(|idx| XY::new(idx, idx)).collect(),
+                (0..rows).map(|idx| XY::new(idx, cols - 1 - idx)).collect(),
+            ]),
+            WinRule::Diagonals => Err(format!(
+                "Diagonals need square boards, but the boards are {}x{}",
+                rows, cols
+            )),
+            WinRule::Corners => Ok(vec![vec![
+                XY::new(0, 0),
+                XY::new(0, cols - 1),
+                XY::new(rows - 1, 0),
+                XY::new(rows - 1, cols - 1),
+            ]]),
+            WinRule::FullCard => Ok(vec![(0..rows)
+                .flat_map(|row| (0..cols).map(move |col| XY::new(row, col)))
+                .collect()]),
+            WinRule::Pattern(mask)
+                if mask.len() == rows && mask.iter().all(|row| row.len() == cols) =>
+            {
+                Ok(vec![(0..rows)
+                    .flat_map(|row| (0..cols).map(move |col| XY::new(row, col)))
+                    .filter(|xy| mask[xy.x][xy.y])
+                    .collect()])
+            }
+            WinRule::Pattern(_) => Err(format!(
+                "Win pattern must be {}x{} to match the boards",
+                rows, cols
+            )),
+        }
+    }
+}
+
+/// Every set of cells that wins under some rules, for one board size, indexed by cell so a mark
+/// only has to check the sets it touches.
+struct WinLines {
+    lines: Vec<Vec<XY<usize>>>,
+    // Mapping of {cell => indices into `lines` of every set containing it}.
+    by_cell: HashMap<XY<usize>, Vec<usize>>,
+}
+
+impl WinLines {
+    fn new(rules: &[WinRule], rows: usize, cols: usize) -> Result<Self, String> {
+        let mut lines = vec![];
+        for rule in rules {
+            lines.extend(rule.lines(rows, cols)?);
+        }
+
+        let mut by_cell: HashMap<XY<usize>, Vec<usize>> = HashMap::new();
+        for (idx, line) in lines.iter().enumerate() {
+            for &cell in line {
+                by_cell.entry(cell).or_default().push(idx);
+            }
+        }
+
+        Ok(WinLines { lines, by_cell })
+    }
+
+    fn through(&self, xy: XY<usize>) -> impl Iterator<Item = &Vec<XY<usize>>> {
+        self.by_cell
+            .get(&xy)
+            .into_iter()
+            .flatten()
+            .map(|&idx| &self.lines[idx])
+    }
+}
+
 #[derive(Debug)]
 struct BingoBoard {
     spaces: Vec<Vec<i64>>,
@@ -16,16 +147,14 @@ struct BingoBoard {
 impl Display for BingoBoard {
     fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
         for row in &self.spaces {
-            if let Err(e) = writeln!(
+            writeln!(
                 f,
                 "{}",
                 row.iter()
                     .map(|val| format!("{:2}", val))
                     .collect::<Vec<String>>()
                     .join(" ")
-            ) {
-                return Err(e);
-            }
+            )?;
         }
 
         Ok(())
@@ -33,93 +162,46 @@ impl Display for BingoBoard {
 }
 
 impl BingoBoard {
-    fn from_data_iter<'a>(iter: &mut impl Iterator<Item = &'a String>) -> Option<Self> {
-        let mut spaces = Vec::with_capacity(5);
-        let mut taken = 0;
-        while taken < 5 {
-            let line = iter.next()?;
-            if line.is_empty() {
-                continue;
-            }
-
-            let numbers: Vec<i64> = line
-                .split(' ')
-                .filter(|val| !val.is_empty())
-                .map(str::parse::<i64>)
-                .map(Result::unwrap)
-                .collect();
-
-            spaces.push(numbers);
-            taken += 1;
-        }
-
-        Some(BingoBoard {
+    /// One board, a row of numbers per line. All rows must be the same length.
+    fn from_block(block: &[String]) -> Self {
+        let spaces = block
+            .iter()
+            .map(|line| parse::integers::<i64>(line).unwrap())
+            .collect::<Vec<Vec<i64>>>();
+
+        assert!(
+            !spaces[0].is_empty() && spaces.iter().all(|row| row.len() == spaces[0].len()),
+            "Board rows must all be the same length."
+        );
+
+        BingoBoard {
+            called: vec![vec![false; spaces[0].len()]; spaces.len()],
             spaces,
-            called: vec![vec![false; 5]; 5],
-        })
-    }
-
-    fn get_locations(&self) -> HashMap<i64, XY<usize>> {
-        let mut res: HashMap<i64, XY<usize>> = HashMap::new();
-
-        for (row_idx, row) in self.spaces.iter().enumerate() {
-            for (col_idx, value) in row.iter().enumerate() {
-                res.insert(*value, XY::new(row_idx, col_idx));
-            }
         }
+    }
 
-        res
+    fn rows(&self) -> usize {
+        self.spaces.len()
     }
 
-    fn call_value(
-        &mut self,
-        value: i64,
-        locations: &HashMap<i64, XY<usize>>,
-        allow_diagonals: bool,
-    ) -> bool {
-        let maybe_xy = locations.get(&value);
-        if maybe_xy.is_none() {
-            return false;
-        }
+    fn cols(&self) -> usize {
+        self.spaces[0].len()
+    }
 
-        let xy = maybe_xy.unwrap();
+    /// Marks the cell at `xy`, returning whether that completes any of `lines`.
+    fn mark(&mut self, xy: XY<usize>, lines: &WinLines) -> bool {
         self.called[xy.x][xy.y] = true;
 
-        // Checks.
-        // Horizontal.
-        if self.called[xy.x].iter().all(|val| *val) {
-            return true;
-        }
-        // Vertical.
-        else if self.called.iter().all(|row| row[xy.y]) {
-            return true;
-        }
-        // Diagonal \.
-        else if allow_diagonals
-            && xy.x == xy.y
-            && self.called.iter().enumerate().all(|(idx, row)| row[idx])
-        {
-            return true;
-        }
-        // Diagonal /.
-        else if allow_diagonals
-            && xy.x + xy.y == 4
-            && self
-                .called
-                .iter()
-                .enumerate()
-                .all(|(idx, row)| row[4 - idx])
-        {
-            return true;
-        }
-
-        false
+        // Only sets containing the new cell can have just been completed.
+        lines
+            .through(xy)
+            .any(|line| line.iter().all(|cell| self.called[cell.x][cell.y]))
     }
 
     fn unmarked_sum(&self) -> i64 {
         let mut sum: i64 = 0;
-        for row in 0usize..=4usize {
-            for col in 0usize..=4usize {
+        for row in 0..self.rows() {
+            for col in 0..self.cols() {
                 if !self.called[row][col] {
                     sum += self.spaces[row][col];
                 }
@@ -136,35 +218,157 @@ struct BingoGame {
 }
 
 impl BingoGame {
-    fn from_data_iter<'a>(iter: &mut impl Iterator<Item = &'a String>) -> Self {
-        let number_order = iter
-            .next()
-            .unwrap()
-            .split(',')
-            .map(str::parse::<i64>)
-            .map(Result::unwrap)
-            .collect();
-
-        let mut boards = vec![];
-        loop {
-            let board = BingoBoard::from_data_iter(iter);
-            if board.is_none() {
+    /// The call order on the first line, then boards separated by blank lines. Boards may be
+    /// any size, but must all be the same size.
+    fn from_data(data: &[String]) -> Self {
+        let number_order = parse::integers(&data[0]).unwrap();
+        let boards = parse::blocks(&data[1..])
+            .into_iter()
+            .map(BingoBoard::from_block)
+            .collect::<Vec<BingoBoard>>();
+
+        assert!(
+            boards
+                .iter()
+                .all(|board| (board.rows(), board.cols()) == (boards[0].rows(), boards[0].cols())),
+            "Boards must all be the same size."
+        );
+
+        BingoGame {
+            number_order,
+            boards,
+        }
+    }
+
+    // Mapping of {value => (board index, position) for every cell holding it}.
+    fn index(&self) -> HashMap<i64, Vec<(usize, XY<usize>)>> {
+        let mut res: HashMap<i64, Vec<(usize, XY<usize>)>> = HashMap::new();
+        for (board_idx, board) in self.boards.iter().enumerate() {
+            for (row_idx, row) in board.spaces.iter().enumerate() {
+                for (col_idx, value) in row.iter().enumerate() {
+                    res.entry(*value)
+                        .or_default()
+                        .push((board_idx, XY::new(row_idx, col_idx)));
+                }
+            }
+        }
+
+        res
+    }
+
+    /// Calls every number once across all boards until each board has won or the numbers run
+    /// out. Boards stop being marked once they have won. Fails if a rule doesn't fit the boards.
+    fn play(&mut self, rules: &[WinRule]) -> Result<GameLog, String> {
+        let lines = match self.boards.first() {
+            Some(board) => WinLines::new(rules, board.rows(), board.cols())?,
+            None => return Ok(GameLog { events: vec![] }),
+        };
+        let index = self.index();
+        let mut won = vec![false; self.boards.len()];
+        let mut events = vec![];
+
+        for (call_idx, &number) in self.number_order.iter().enumerate() {
+            if won.iter().all(|&won| won) {
                 break;
             }
 
-            boards.push(board.unwrap());
+            events.push(BingoEvent::Called {
+                call: call_idx + 1,
+                number,
+            });
+            for &(board_idx, position) in index.get(&number).into_iter().flatten() {
+                if won[board_idx] {
+                    continue;
+                }
+
+                events.push(BingoEvent::Marked {
+                    board: board_idx,
+                    position,
+                });
+                let board = &mut self.boards[board_idx];
+                if board.mark(position, &lines) {
+                    won[board_idx] = true;
+                    events.push(BingoEvent::Won {
+                        board: board_idx,
+                        call: call_idx + 1,
+                        score: number * board.unmarked_sum(),
+                    });
+                }
+            }
         }
 
-        BingoGame {
-            number_order,
-            boards,
+        Ok(GameLog { events })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum BingoEvent {
+    /// `call` counts from 1.
+    Called {
+        call: usize,
+        number: i64,
+    },
+    Marked {
+        board: usize,
+        position: XY<usize>,
+    },
+    Won {
+        board: usize,
+        call: usize,
+        score: i64,
+    },
+}
+
+impl Display for BingoEvent {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            BingoEvent::Called { call, number } => write!(f, "Call {}: {}", call, number),
+            BingoEvent::Marked { board, position } => write!(
+                f,
+                "  Board {} marks row {}, column {}",
+                board + 1,
+                position.x + 1,
+                position.y + 1
+            ),
+            BingoEvent::Won { board, score, .. } => {
+                write!(f, "  Board {} wins with score {}", board + 1, score)
+            }
         }
     }
 }
 
+/// Everything that happened in a game, in order.
+struct GameLog {
+    events: Vec<BingoEvent>,
+}
+
+impl GameLog {
+    /// (board, score) in the order the boards won. Boards completing on the same call are
+    /// ordered by board index.
+    fn winners(&self) -> impl Iterator<Item = (usize, i64)> + '_ {
+        self.events.iter().filter_map(|event| match event {
+            BingoEvent::Won { board, score, .. } => Some((*board, *score)),
+            _ => None,
+        })
+    }
+
+    /// The `k`th board to win, counting from 1.
+    fn kth_winner(&self, k: usize) -> Option<(usize, i64)> {
+        self.winners().nth(k.checked_sub(1)?)
+    }
+
+    fn first_winner(&self) -> Option<(usize, i64)> {
+        self.winners().next()
+    }
+
+    fn last_winner(&self) -> Option<(usize, i64)> {
+        self.winners().last()
+    }
+}
+
 impl Display for BingoGame {
     fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
-        if let Err(e) = writeln!(
+        writeln!(
             f,
             "Call order: {}",
             self.number_order
@@ -172,72 +376,176 @@ impl Display for BingoGame {
                 .map(|val| val.to_string())
                 .collect::<Vec<String>>()
                 .join(", ")
-        ) {
-            return Err(e);
-        }
-
-        if let Err(e) = writeln!(f) {
-            return Err(e);
-        }
+        )?;
+        writeln!(f)?;
 
         for board in &self.boards {
-            if let Err(e) = writeln!(f, "{}", board) {
-                return Err(e);
-            }
+            writeln!(f, "{}", board)?;
         }
 
         Ok(())
     }
 }
 
+pub fn run_cli(options: &[String]) -> Result<(), String> {
+    let args = Args::parse(options, &["rules", "data", "kth", "log"])?;
+
+    let rules = args
+        .get_or("rules", "rows,columns".to_string())?
+        .split(',')
+        .map(WinRule::from_name)
+        .collect::<Result<Vec<WinRule>, String>>()?;
+
+    let data = match args.get_or("data", "input".to_string())?.as_str() {
+        "sample" => Day4::sample_data(),
+        "input" => Day4::input_data(),
+        other => return Err(format!("Unknown data set \"{}\"", other)),
+    };
+    let mut game = BingoGame::from_data(&data);
+    let log = game.play(&rules)?;
+
+    if args.get_or("log", false)? {
+        for event in &log.events {
+            println!("{}", event);
+        }
+    }
+
+    let describe = |winner: Option<(usize, i64)>| match winner {
+        Some((board, score)) => format!("board {} with score {}", board + 1, score),
+        None => "nobody".to_string(),
+    };
+    println!("First winner: {}", describe(log.first_winner()));
+    println!("Last winner: {}", describe(log.last_winner()));
+    if let Some(k) = args.get("kth")? {
+        println!("Winner {}: {}", k, describe(log.kth_winner(k)));
+    }
+
+    Ok(())
+}
+
 impl Solution for Day4 {
     fn day_number() -> i32 {
         4
     }
 
     fn solution_impl_1(data: Vec<String>) -> Option<i64> {
-        let mut bingo_game = BingoGame::from_data_iter(&mut data.iter());
-
-        let order = &bingo_game.number_order;
-        let mut lowest: Option<usize> = None;
-        let mut score: i64 = 0;
-        for board in bingo_game.boards.iter_mut() {
-            for (num_idx, num) in order.iter().enumerate() {
-                let locations = board.get_locations();
-                let bingo = board.call_value(*num, &locations, false);
-                if bingo {
-                    if lowest.is_none() || num_idx + 1 < lowest.unwrap() {
-                        lowest = Some(num_idx + 1);
-                        score = num * board.unmarked_sum();
-                    }
-                    break;
-                }
-            }
-        }
-
-        Some(score)
+        let mut bingo_game = BingoGame::from_data(&data);
+        let log = bingo_game.play(&[WinRule::Rows, WinRule::Columns]).ok()?;
+        Some(log.first_winner()?.1)
     }
 
     fn solution_impl_2(data: Vec<String>) -> Option<i64> {
-        let mut bingo_game = BingoGame::from_data_iter(&mut data.iter());
-
-        let order = &bingo_game.number_order;
-        let mut highest: Option<usize> = None;
-        let mut score: i64 = 0;
-        for board in bingo_game.boards.iter_mut() {
-            for (num_idx, num) in order.iter().enumerate() {
-                let locations = board.get_locations();
-                let bingo = board.call_value(*num, &locations, false);
-                if bingo {
-                    if highest.is_none() || num_idx + 1 > highest.unwrap() {
-                        highest = Some(num_idx + 1);
-                        score = num * board.unmarked_sum();
-                    }
-                    break;
-                }
-            }
-        }
+        let mut bingo_game = BingoGame::from_data(&data);
+        let log = bingo_game.play(&[WinRule::Rows, WinRule::Columns]).ok()?;
+        Some(log.last_winner()?.1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game(data: &[&str]) -> BingoGame {
+        BingoGame::from_data(
+            &data
+                .iter()
+                .map(|line| line.to_string())
+                .collect::<Vec<String>>(),
+        )
+    }
+
+    #[test]
+    fn rules_must_fit_the_boards() {
+        let mut square = game(&["1,2,3,4", "", "1 2", "3 4"]);
+        let mut wide = game(&["1,2,3,4,5,6", "", "1 2 3", "4 5 6"]);
+
+        let pattern = WinRule::from_name("pattern:#./.#").unwrap();
+        assert!(square.play(std::slice::from_ref(&pattern)).is_ok());
+        assert!(wide.play(&[pattern]).is_err());
+        assert!(wide.play(&[WinRule::Diagonals]).is_err());
+        assert!(wide.play(&[WinRule::Rows, WinRule::Corners]).is_ok());
+    }
+
+    #[test]
+    fn patterns_need_a_cell() {
+        assert!(WinRule::from_name("pattern:#.").is_ok());
+        assert_eq!(
+            WinRule::from_name("pattern:../.."),
+            Err("Win pattern needs at least one '#'".to_string())
+        );
+        assert!(WinRule::from_name("pattern:").is_err());
+        assert!(WinRule::from_name("pattern:#x").is_err());
+    }
+
+    #[test]
+    fn rules_decide_the_winner() {
+        let square = || game(&["1,4,2", "", "1 2", "3 4"]);
+        let log = square().play(&[WinRule::Diagonals]).unwrap();
+        assert_eq!(log.first_winner(), Some((0, 4 * 5)));
+
+        let log = square().play(&[WinRule::FullCard]).unwrap();
+        assert_eq!(log.first_winner(), None);
+    }
+
+    #[test]
+    fn log_records_every_call_mark_and_win() {
+        let mut game = game(&[
+            "1,2,5,6,3,4,8,9",
+            "",
+            "1 2",
+            "3 4",
+            "",
+            "5 6",
+            "7 1",
+            "",
+            "9 9",
+            "8 8",
+        ]);
+        let log = game.play(&[WinRule::Rows]).unwrap();
+
+        let called = |call, number| BingoEvent::Called { call, number };
+        let marked = |board, row, col| BingoEvent::Marked {
+            board,
+            position: XY::new(row, col),
+        };
+        let won = |board, call, score| BingoEvent::Won { board, call, score };
+        assert_eq!(
+            log.events,
+            vec![
+                called(1, 1),
+                marked(0, 0, 0),
+                marked(1, 1, 1),
+                called(2, 2),
+                marked(0, 0, 1),
+                won(0, 2, 2 * (3 + 4)),
+                called(3, 5),
+                marked(1, 0, 0),
+                called(4, 6),
+                marked(1, 0, 1),
+                won(1, 4, 6 * 7),
+                // Board 0 has already won, so it is not marked any more.
+                called(5, 3),
+                called(6, 4),
+                called(7, 8),
+                marked(2, 1, 0),
+                marked(2, 1, 1),
+                won(2, 7, 8 * (9 + 9)),
+            ]
+        );
+        assert!(!game.boards[0].called[1][0]);
+
+        assert_eq!(log.kth_winner(0), None);
+        assert_eq!(log.kth_winner(1), Some((0, 14)));
+        assert_eq!(log.kth_winner(2), Some((1, 42)));
+        assert_eq!(log.kth_winner(3), Some((2, 144)));
+        assert_eq!(log.kth_winner(4), None);
+        assert_eq!(log.first_winner(), log.kth_winner(1));
+        assert_eq!(log.last_winner(), log.kth_winner(3));
+    }
 
-        Some(score)
+    #[test]
+    fn sample() {
+        assert_eq!(Day4::solution_impl_1(Day4::sample_data()), Some(4512));
+        assert_eq!(Day4::solution_impl_2(Day4::sample_data()), Some(1924));
     }
 }
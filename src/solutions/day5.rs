@@ -1,5 +1,6 @@
+use crate::utils::parse;
+use crate::utils::sparse_grid::SparseGrid;
 use crate::utils::{Solution, XY};
-use std::collections::HashSet;
 use std::fmt::{Display, Formatter};
 
 pub struct Day5;
@@ -9,59 +10,27 @@ struct Line {
     end: XY<i64>,
 }
 
-fn range(start: i64, end: i64, include_end: bool) -> Vec<i64> {
-    let order = if start < end {
-        (start, end)
-    } else {
-        (end, start)
-    };
-
-    let vec = if include_end {
-        (order.0..=order.1).collect()
-    } else {
-        (order.0..order.1).collect()
-    };
-
-    if start == order.0 {
-        vec
-    } else {
-        vec.into_iter().rev().collect()
-    }
-}
-
 impl Line {
     pub fn new(start: XY<i64>, end: XY<i64>) -> Self {
         Line { start, end }
     }
 
     pub fn get_points(&self, allow_diagonals: bool) -> Vec<XY<i64>> {
-        if self.start.x == self.end.x {
-            range(self.start.y, self.end.y, true)
-                .into_iter()
-                .map(|y| XY::new(self.start.x, y))
-                .collect()
-        } else if self.start.y == self.end.y {
-            range(self.start.x, self.end.x, true)
-                .into_iter()
-                .map(|x| XY::new(x, self.start.y))
-                .collect()
-        } else if !allow_diagonals {
-            vec![]
-        } else {
-            range(self.start.x, self.end.x, true)
-                .into_iter()
-                .zip(range(self.start.y, self.end.y, true))
-                .map(|(x, y)| XY::new(x, y))
-                .collect()
+        let delta = self.end - self.start;
+        if !allow_diagonals && delta.x != 0 && delta.y != 0 {
+            return vec![];
         }
+
+        // Lines are only ever horizontal, vertical or at 45 degrees.
+        let step = delta.signum();
+        (0..=self.start.chebyshev(&self.end).unwrap())
+            .map(|idx| self.start + step * idx)
+            .collect()
     }
 
     fn from_string(s: &str) -> Self {
-        let splits = s.split(" -> ").collect::<Vec<&str>>();
-        assert_eq!(splits.len(), 2, "Line \"{}\" malformed", s);
-
-        let mut xys = splits.into_iter().map(XY::from).collect::<Vec<XY<i64>>>();
-        Line::new(xys.pop().unwrap(), xys.pop().unwrap())
+        let [x1, y1, x2, y2] = parse::integers_array::<i64, 4>(s).unwrap();
+        Line::new(XY::new(x1, y1), XY::new(x2, y2))
     }
 }
 
@@ -71,6 +40,18 @@ impl Display for Line {
     }
 }
 
+fn count_overlaps(lines: &[Line], allow_diagonals: bool) -> i64 {
+    // Mapping of {point => number of lines covering it}.
+    let mut vents: SparseGrid<i64> = SparseGrid::new(0);
+    for line in lines {
+        for point in line.get_points(allow_diagonals) {
+            *vents.get_mut(point) += 1;
+        }
+    }
+
+    vents.values().filter(|&&count| count > 1).count() as i64
+}
+
 impl Solution for Day5 {
     fn day_number() -> i32 {
         5
@@ -83,19 +64,7 @@ impl Solution for Day5 {
             .map(Line::from_string)
             .collect::<Vec<Line>>();
 
-        let mut seen: HashSet<XY<i64>> = HashSet::new();
-        let mut duped: HashSet<XY<i64>> = HashSet::new();
-        for line in lines {
-            for point in line.get_points(false) {
-                if seen.contains(&point) {
-                    duped.insert(point);
-                } else {
-                    seen.insert(point);
-                }
-            }
-        }
-
-        Some(duped.len() as i64)
+        Some(count_overlaps(&lines, false))
     }
 
     fn solution_impl_2(data: Vec<String>) -> Option<i64> {
@@ -105,18 +74,6 @@ impl Solution for Day5 {
             .map(Line::from_string)
             .collect::<Vec<Line>>();
 
-        let mut seen: HashSet<XY<i64>> = HashSet::new();
-        let mut duped: HashSet<XY<i64>> = HashSet::new();
-        for line in lines {
-            for point in line.get_points(true) {
-                if seen.contains(&point) {
-                    duped.insert(point);
-                } else {
-                    seen.insert(point);
-                }
-            }
-        }
-
-        Some(duped.len() as i64)
+        Some(count_overlaps(&lines, true))
     }
 }
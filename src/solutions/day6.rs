@@ -1,56 +1,258 @@
-use std::collections::hash_map::Entry;
-use std::collections::HashMap;
+use std::io::Write;
 
+use crate::utils::automaton::{Automaton, Simulation};
+use crate::utils::bigint::BigUint;
+use crate::utils::checked::OverflowError;
+use crate::utils::cli::Args;
+use crate::utils::counter::Counter;
+use crate::utils::matrix::{LinearRecurrence, Matrix, Scalar};
+use crate::utils::parse;
 use crate::utils::Solution;
 
 pub struct Day6;
 
+const PART_1_DAYS: u64 = 80;
+const PART_2_DAYS: u64 = 256;
+// History is stepped one day at a time, and rows grow with the population's digit count.
+const MAX_HISTORY_DAYS: u64 = 10_000;
+
+#[derive(Clone, Copy, Debug)]
+struct LanternfishConfig {
+    // Days between each of a fish's spawns.
+    spawn_cycle: i64,
+    // Extra days a newborn waits before starting its first cycle.
+    newborn_delay: i64,
+}
+
+impl Default for LanternfishConfig {
+    fn default() -> Self {
+        LanternfishConfig {
+            spawn_cycle: 7,
+            newborn_delay: 2,
+        }
+    }
+}
+
+impl LanternfishConfig {
+    fn reset_timer(&self) -> i64 {
+        self.spawn_cycle - 1
+    }
+
+    fn newborn_timer(&self) -> i64 {
+        self.reset_timer() + self.newborn_delay
+    }
+}
+
 struct LanternfishAutomata {
+    config: LanternfishConfig,
     // Mapping of {counter_value => number of lanternfish}.
-    counters: HashMap<i64, i64>,
+    counters: Counter<i64>,
 }
 
 impl LanternfishAutomata {
-    fn from_input(s: &str) -> Self {
-        let mut counters: HashMap<i64, i64> = HashMap::new();
-        for counter in s.split(',').map(str::parse::<i64>).map(Result::unwrap) {
-            match counters.entry(counter) {
-                Entry::Occupied(mut e) => {
-                    e.insert(e.get() + 1);
-                }
-                Entry::Vacant(e) => {
-                    e.insert(1);
-                }
-            };
+    fn from_input(s: &str, config: LanternfishConfig) -> Self {
+        LanternfishAutomata {
+            config,
+            counters: parse::integers::<i64>(s).unwrap().into_iter().collect(),
         }
+    }
 
-        LanternfishAutomata { counters }
+    fn size(&self) -> Result<i64, OverflowError> {
+        self.counters.try_total()
     }
 
-    fn step(&mut self) {
-        let new_fish = *self.counters.get(&0).unwrap_or(&0);
-        for counter in 1..=8 {
-            self.counters
-                .insert(counter - 1, *self.counters.get(&counter).unwrap_or(&0));
+    /// Number of distinct timer values a fish can have, i.e. 0 up to the newborn timer or the
+    /// largest timer in the input.
+    fn buckets(&self) -> usize {
+        let largest = self.counters.keys().next_back().copied().unwrap_or(0);
+        (largest.max(self.config.newborn_timer()) + 1) as usize
+    }
+
+    /// Transition acting on the vector of fish counts per timer value.
+    fn transition<T: Scalar>(&self) -> Matrix<T> {
+        let mut res = Matrix::zero(self.buckets());
+        for counter in 1..self.buckets() {
+            res.set(counter - 1, counter, T::one());
         }
 
-        match self.counters.entry(6) {
-            Entry::Occupied(mut e) => {
-                e.insert(e.get() + new_fish);
-            }
-            Entry::Vacant(e) => {
-                e.insert(new_fish);
-            }
+        // Spawning resets the parent, which may coincide with the newborn timer.
+        let reset = self.config.reset_timer() as usize;
+        let newborn = self.config.newborn_timer() as usize;
+        res.set(reset, 0, T::one());
+        res.set(newborn, 0, res.get(newborn, 0).add(&T::one()));
+
+        res
+    }
+
+    fn timer_counts(&self) -> Vec<u64> {
+        (0..self.buckets() as i64)
+            .map(|counter| self.counters.get(&counter) as u64)
+            .collect()
+    }
+
+    /// Exact population after `days`, in O(log days) matrix multiplications.
+    fn size_after(&self, days: u64) -> BigUint {
+        let state = self
+            .timer_counts()
+            .into_iter()
+            .map(BigUint::from)
+            .collect::<Vec<BigUint>>();
+
+        LinearRecurrence::new(self.transition())
+            .advance(&state, days)
+            .into_iter()
+            .fold(BigUint::zero(), |sum, count| sum + count)
+    }
+
+    /// Population after `days`, modulo `modulus`.
+    fn size_after_mod(&self, days: u64, modulus: u64) -> u64 {
+        LinearRecurrence::new(self.transition())
+            .advance_mod(&self.timer_counts(), days, modulus)
+            .into_iter()
+            .fold(0, |sum, count| {
+                ((sum as u128 + count as u128) % modulus as u128) as u64
+            })
+    }
+
+    /// Writes the fish count in every timer bucket for each day from 0 to `days` as CSV. Counts
+    /// are exact, so they keep growing past what fits in an i64.
+    fn write_history(&self, days: usize, out: &mut impl Write) -> std::io::Result<()> {
+        let buckets = self.buckets();
+        let initial = TimerVector {
+            transition: self.transition(),
+            counts: self.timer_counts().into_iter().map(BigUint::from).collect(),
+        };
+
+        let mut rows: Vec<String> = vec![initial.history_row(0)];
+        let mut simulation = Simulation::new(initial)
+            .with_observer(|day, state: &TimerVector, _| rows.push(state.history_row(day)));
+        simulation.run(days);
+        drop(simulation);
+
+        let timers = (0..buckets)
+            .map(|counter| format!("timer_{}", counter))
+            .collect::<Vec<String>>();
+        writeln!(out, "day,{},total", timers.join(","))?;
+        for row in rows {
+            writeln!(out, "{}", row)?;
         }
 
-        self.counters.insert(8, new_fish);
+        Ok(())
     }
+}
+
+/// Exact fish counts per timer value, stepped one day at a time by the transition matrix.
+struct TimerVector {
+    transition: Matrix<BigUint>,
+    counts: Vec<BigUint>,
+}
+
+impl TimerVector {
+    fn history_row(&self, day: usize) -> String {
+        let total = self
+            .counts
+            .iter()
+            .fold(BigUint::zero(), |sum, count| &sum + count);
 
-    fn size(&self) -> i64 {
-        self.counters.values().sum()
+        let mut row = vec![day.to_string()];
+        row.extend(self.counts.iter().map(BigUint::to_string));
+        row.push(total.to_string());
+        row.join(",")
     }
 }
 
+impl Automaton for TimerVector {
+    // Number of lanternfish born.
+    type Stats = BigUint;
+
+    fn step(&mut self) -> BigUint {
+        let new_fish = self.counts[0].clone();
+        self.counts = self.transition.apply(&self.counts);
+        new_fish
+    }
+}
+
+impl Automaton for LanternfishAutomata {
+    // Number of lanternfish born, or why the day couldn't be simulated. Overflowing days leave
+    // the state as it was.
+    type Stats = Result<i64, OverflowError>;
+
+    fn step(&mut self) -> Result<i64, OverflowError> {
+        let new_fish = self.counters.get(&0);
+        let mut next = Counter::from_counts(
+            self.counters
+                .iter()
+                .filter(|(&counter, _)| counter > 0)
+                .map(|(counter, count)| (counter - 1, count)),
+        );
+
+        next.try_increment(self.config.reset_timer(), new_fish)?;
+        next.try_increment(self.config.newborn_timer(), new_fish)?;
+        self.counters = next;
+        Ok(new_fish)
+    }
+}
+
+/// Options: --spawn-cycle N, --newborn-delay N, --days N, --data sample|input, --modulus M and
+/// --csv PATH (or "-" for stdout) to dump the per-day timer counts.
+pub fn run_cli(options: &[String]) -> Result<(), String> {
+    let args = Args::parse(
+        options,
+        &[
+            "spawn-cycle",
+            "newborn-delay",
+            "days",
+            "data",
+            "modulus",
+            "csv",
+        ],
+    )?;
+
+    let default = LanternfishConfig::default();
+    let config = LanternfishConfig {
+        spawn_cycle: args.get_or("spawn-cycle", default.spawn_cycle)?,
+        newborn_delay: args.get_or("newborn-delay", default.newborn_delay)?,
+    };
+    if config.spawn_cycle < 1 || config.newborn_delay < 0 {
+        return Err("Spawn cycle must be positive and newborn delay non-negative".to_string());
+    }
+
+    let data = match args.get_or("data", "input".to_string())?.as_str() {
+        "sample" => Day6::sample_data(),
+        "input" => Day6::input_data(),
+        other => return Err(format!("Unknown data set \"{}\"", other)),
+    };
+    let automata = LanternfishAutomata::from_input(&data[0], config);
+
+    let days = args.get_or("days", PART_1_DAYS)?;
+    let csv = args.get::<String>("csv")?;
+    if csv.is_some() && days > MAX_HISTORY_DAYS {
+        return Err(format!(
+            "--csv writes a row per day, so --days must be at most {}",
+            MAX_HISTORY_DAYS
+        ));
+    }
+
+    let size = match args.get::<u64>("modulus")? {
+        Some(0) => return Err("Modulus must be positive".to_string()),
+        Some(modulus) => automata.size_after_mod(days, modulus).to_string(),
+        None => automata.size_after(days).to_string(),
+    };
+    println!("Population after {} days: {}", days, size);
+
+    if let Some(path) = csv {
+        let res = if path == "-" {
+            automata.write_history(days as usize, &mut std::io::stdout())
+        } else {
+            std::fs::File::create(&path)
+                .and_then(|mut file| automata.write_history(days as usize, &mut file))
+        };
+        res.map_err(|error| format!("Failed to write history to {}: {}", path, error))?;
+    }
+
+    Ok(())
+}
+
 impl Solution for Day6 {
     fn day_number() -> i32 {
         6
@@ -63,12 +265,15 @@ impl Solution for Day6 {
             "Lanternfish automata requires one line of input"
         );
 
-        let mut automata = LanternfishAutomata::from_input(&data[0]);
-        for _ in 0..80 {
-            automata.step();
-        }
+        let automata = LanternfishAutomata::from_input(&data[0], LanternfishConfig::default());
+        let mut simulation = Simulation::new(automata);
+        simulation
+            .run(PART_1_DAYS as usize)
+            .into_iter()
+            .collect::<Result<Vec<i64>, OverflowError>>()
+            .ok()?;
 
-        Some(automata.size())
+        simulation.state.size().ok()
     }
 
     fn solution_impl_2(data: Vec<String>) -> Option<i64> {
@@ -78,11 +283,37 @@ impl Solution for Day6 {
             "Lanternfish automata requires one line of input"
         );
 
-        let mut automata = LanternfishAutomata::from_input(&data[0]);
-        for _ in 0..256 {
-            automata.step();
-        }
+        let automata = LanternfishAutomata::from_input(&data[0], LanternfishConfig::default());
+        i64::try_from(&automata.size_after(PART_2_DAYS)).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> LanternfishAutomata {
+        LanternfishAutomata::from_input(&Day6::sample_data()[0], LanternfishConfig::default())
+    }
+
+    #[test]
+    fn history_matches_stepping() {
+        let mut out = vec![];
+        sample().write_history(18, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines = out.lines().collect::<Vec<&str>>();
+        assert_eq!(lines.len(), 20);
+        assert_eq!(lines[1], "0,0,1,1,2,1,0,0,0,0,5");
+        assert!(lines[19].ends_with(",26"));
+    }
 
-        Some(automata.size())
+    #[test]
+    fn history_outgrows_i64() {
+        let mut out = vec![];
+        sample().write_history(500, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let total = out.lines().last().unwrap().rsplit(',').next().unwrap();
+        assert_eq!(total, sample().size_after(500).to_string());
+        assert!(total.parse::<i64>().is_err());
     }
 }
@@ -1,48 +1,367 @@
-use std::collections::hash_map::Entry;
+use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
 
+use crate::utils::checked::{checked_sum, CheckedArith, OverflowError};
+use crate::utils::cli::Args;
+use crate::utils::counter::Counter;
+use crate::utils::parse;
 use crate::utils::Solution;
 
 pub struct Day7;
 
-fn counterize(data: Vec<String>) -> HashMap<i64, i64> {
+fn counterize(data: Vec<String>) -> Counter<i64> {
     assert_eq!(data.len(), 1, "Input data must have only one line of input");
-    let mut res: HashMap<i64, i64> = HashMap::new();
-    for val in data[0]
-        .split(',')
-        .map(str::parse::<i64>)
-        .map(Result::unwrap)
-        .collect::<Vec<i64>>()
-    {
-        match res.entry(val) {
-            Entry::Occupied(mut e) => {
-                e.insert(*e.get() + 1);
-            }
-            Entry::Vacant(e) => {
-                e.insert(1);
-            }
+    parse::integers::<i64>(&data[0])
+        .unwrap()
+        .into_iter()
+        .collect()
+}
+
+fn bounds(counts: &Counter<i64>) -> Option<(i64, i64)> {
+    Some((*counts.keys().next()?, *counts.keys().next_back()?))
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum AlignError {
+    NoCrabs,
+    /// Fuel for some position was too large to total.
+    Overflow(OverflowError),
+}
+
+impl Display for AlignError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            AlignError::NoCrabs => write!(f, "No crabs to align"),
+            AlignError::Overflow(error) => write!(f, "{}", error),
         }
     }
+}
+
+impl std::error::Error for AlignError {}
+
+impl From<OverflowError> for AlignError {
+    fn from(error: OverflowError) -> Self {
+        AlignError::Overflow(error)
+    }
+}
+
+/// How much fuel one crab burns moving from `crab` to `target`.
+pub trait FuelCost {
+    fn fuel(&self, crab: i64, target: i64) -> Result<i64, OverflowError>;
+
+    /// Whether the total fuel is convex in the target, so ternary search finds the optimum.
+    /// Models must opt in; anything else is searched exhaustively.
+    fn is_convex(&self) -> bool {
+        false
+    }
 
-    res
+    /// Cheapest meeting position. Models with a closed form override this.
+    fn best_position(&self, counts: &Counter<i64>) -> Result<i64, AlignError> {
+        let (min, max) = bounds(counts).ok_or(AlignError::NoCrabs)?;
+        let cost = |target| total_fuel(counts, self, target);
+        let best = if self.is_convex() {
+            ternary_search(min, max, cost)?
+        } else {
+            brute_force(min, max, cost)?
+        };
+
+        best.ok_or(AlignError::NoCrabs)
+    }
 }
 
-fn get_abs_delta_sum(counts: &HashMap<i64, i64>, endpoint: i64) -> i64 {
-    counts
+pub fn total_fuel<M: FuelCost + ?Sized>(
+    counts: &Counter<i64>,
+    model: &M,
+    target: i64,
+) -> Result<i64, OverflowError> {
+    let costs = counts
         .iter()
-        .map(|(value, count)| count * (value - endpoint).abs())
-        .sum()
+        .map(|(crab, count)| count.try_mul(model.fuel(*crab, target)?))
+        .collect::<Result<Vec<i64>, OverflowError>>()?;
+    checked_sum(costs)
+}
+
+fn distance(crab: i64, target: i64) -> Result<i64, OverflowError> {
+    i64::try_from(crab.abs_diff(target)).map_err(|_| {
+        OverflowError::new(format!(
+            "distance from {} to {} does not fit in i64",
+            crab, target
+        ))
+    })
+}
+
+fn triangle(val: i64) -> Result<i64, OverflowError> {
+    // Halve whichever factor is even first so the product only overflows if the result does.
+    if val % 2 == 0 {
+        (val / 2).try_mul(val.try_add(1)?)
+    } else {
+        val.try_mul(val.try_add(1)? / 2)
+    }
+}
+
+/// One unit of fuel per step.
+pub struct Linear;
+
+impl FuelCost for Linear {
+    fn fuel(&self, crab: i64, target: i64) -> Result<i64, OverflowError> {
+        distance(crab, target)
+    }
+
+    fn is_convex(&self) -> bool {
+        true
+    }
+
+    /// Linear fuel cost is minimised at the weighted median of the crab positions.
+    fn best_position(&self, counts: &Counter<i64>) -> Result<i64, AlignError> {
+        let total = counts.try_total()?;
+        let mut seen = 0;
+        let (median, _) = counts
+            .iter()
+            .find(|(_, count)| {
+                seen += count;
+                seen >= total - seen
+            })
+            .ok_or(AlignError::NoCrabs)?;
+
+        Ok(*median)
+    }
+}
+
+/// Each step costs one more than the last.
+pub struct Triangular;
+
+impl FuelCost for Triangular {
+    fn fuel(&self, crab: i64, target: i64) -> Result<i64, OverflowError> {
+        triangle(distance(crab, target)?)
+    }
+
+    fn is_convex(&self) -> bool {
+        true
+    }
+
+    /// Triangular fuel cost is within half a step of the weighted mean at its real minimum, so
+    /// the best integer position is one of the few around it.
+    fn best_position(&self, counts: &Counter<i64>) -> Result<i64, AlignError> {
+        let (min, max) = bounds(counts).ok_or(AlignError::NoCrabs)?;
+        let weighted: i128 = counts
+            .iter()
+            .map(|(value, count)| *value as i128 * count as i128)
+            .sum();
+        // A mean of i64 values always fits in an i64.
+        let mean = weighted.div_euclid(counts.try_total()? as i128) as i64;
+
+        brute_force((mean - 1).max(min), (mean + 2).min(max), |target| {
+            total_fuel(counts, self, target)
+        })?
+        .ok_or(AlignError::NoCrabs)
+    }
+}
+
+/// Squared distance.
+pub struct Quadratic;
+
+impl FuelCost for Quadratic {
+    fn fuel(&self, crab: i64, target: i64) -> Result<i64, OverflowError> {
+        distance(crab, target)?.try_pow(2)
+    }
+
+    fn is_convex(&self) -> bool {
+        true
+    }
+}
+
+/// Another model, but no crab ever burns more than `cap`.
+pub struct Capped {
+    pub inner: Box<dyn FuelCost>,
+    pub cap: i64,
+}
+
+impl FuelCost for Capped {
+    fn fuel(&self, crab: i64, target: i64) -> Result<i64, OverflowError> {
+        Ok(self.inner.fuel(crab, target)?.min(self.cap))
+    }
+}
+
+/// Another model, scaled per crab position. Crabs without a weight count once.
+pub struct Weighted {
+    pub inner: Box<dyn FuelCost>,
+    // Mapping of {crab position => fuel multiplier}.
+    pub weights: HashMap<i64, i64>,
+}
+
+impl FuelCost for Weighted {
+    fn fuel(&self, crab: i64, target: i64) -> Result<i64, OverflowError> {
+        let weight = *self.weights.get(&crab).unwrap_or(&1);
+        self.inner.fuel(crab, target)?.try_mul(weight)
+    }
+
+    fn is_convex(&self) -> bool {
+        self.inner.is_convex() && self.weights.values().all(|&weight| weight >= 0)
+    }
+}
+
+/// Arbitrary cost of the distance travelled.
+pub struct Custom<F: Fn(i64) -> Result<i64, OverflowError>> {
+    pub cost: F,
+    pub convex: bool,
+}
+
+impl<F: Fn(i64) -> Result<i64, OverflowError>> FuelCost for Custom<F> {
+    fn fuel(&self, crab: i64, target: i64) -> Result<i64, OverflowError> {
+        (self.cost)(distance(crab, target)?)
+    }
+
+    fn is_convex(&self) -> bool {
+        self.convex
+    }
+}
+
+/// Position with the lowest `cost` in `lo..=hi`, trying every one. The first of several equally
+/// cheap positions wins.
+fn brute_force(
+    lo: i64,
+    hi: i64,
+    cost: impl Fn(i64) -> Result<i64, OverflowError>,
+) -> Result<Option<i64>, OverflowError> {
+    let mut best: Option<(i64, i64)> = None;
+    for target in lo..=hi {
+        let fuel = cost(target)?;
+        if best.is_none_or(|(_, best_fuel)| fuel < best_fuel) {
+            best = Some((target, fuel));
+        }
+    }
+
+    Ok(best.map(|(target, _)| target))
 }
 
-fn triangle(val: i64) -> i64 {
-    val * (val + 1) / 2
+/// Position with the lowest convex `cost` in `lo..=hi`, in O(log(hi - lo)) evaluations.
+fn ternary_search(
+    mut lo: i64,
+    mut hi: i64,
+    cost: impl Fn(i64) -> Result<i64, OverflowError>,
+) -> Result<Option<i64>, OverflowError> {
+    while hi - lo > 2 {
+        let third = (hi - lo) / 3;
+        let (mid_lo, mid_hi) = (lo + third, hi - third);
+        match cost(mid_lo)?.cmp(&cost(mid_hi)?) {
+            // Everything from mid_hi up is at least cost(mid_hi) by convexity.
+            Ordering::Less => hi = mid_hi - 1,
+            Ordering::Greater => lo = mid_lo + 1,
+            // A minimum lies between the two probes.
+            Ordering::Equal => (lo, hi) = (mid_lo, mid_hi),
+        }
+    }
+
+    brute_force(lo, hi, cost)
+}
+
+#[derive(Debug)]
+pub struct CrabFuel {
+    pub position: i64,
+    pub count: i64,
+    /// Fuel burnt by all crabs starting at `position` together.
+    pub fuel: i64,
+}
+
+#[derive(Debug)]
+pub struct Alignment {
+    pub position: i64,
+    pub total: i64,
+    pub breakdown: Vec<CrabFuel>,
 }
 
-fn get_abs_delta_triangle_sum(counts: &HashMap<i64, i64>, endpoint: i64) -> i64 {
-    counts
+pub fn align<M: FuelCost + ?Sized>(
+    counts: &Counter<i64>,
+    model: &M,
+) -> Result<Alignment, AlignError> {
+    let position = model.best_position(counts)?;
+
+    let breakdown = counts
         .iter()
-        .map(|(value, count)| count * triangle((value - endpoint).abs()))
-        .sum()
+        .map(|(&crab, count)| {
+            Ok(CrabFuel {
+                position: crab,
+                count,
+                fuel: count.try_mul(model.fuel(crab, position)?)?,
+            })
+        })
+        .collect::<Result<Vec<CrabFuel>, OverflowError>>()?;
+
+    Ok(Alignment {
+        position,
+        total: checked_sum(breakdown.iter().map(|crab| crab.fuel))?,
+        breakdown,
+    })
+}
+
+fn parse_model(name: &str) -> Result<Box<dyn FuelCost>, String> {
+    match name {
+        "linear" => Ok(Box::new(Linear)),
+        "triangular" => Ok(Box::new(Triangular)),
+        "quadratic" => Ok(Box::new(Quadratic)),
+        other => {
+            // Distance raised to any power, e.g. power:3.
+            let exp = other
+                .strip_prefix("power:")
+                .and_then(|exp| exp.parse::<u32>().ok())
+                .ok_or_else(|| format!("Unknown fuel model \"{}\"", other))?;
+            Ok(Box::new(Custom {
+                cost: move |distance: i64| distance.try_pow(exp),
+                convex: exp >= 1,
+            }))
+        }
+    }
+}
+
+/// Weights given as `position:weight` pairs separated by commas. Weights can't be negative.
+fn parse_weights(s: &str) -> Result<HashMap<i64, i64>, String> {
+    s.split(',')
+        .map(|pair| {
+            let (position, weight) = pair
+                .split_once(':')
+                .ok_or_else(|| format!("Expected position:weight, found \"{}\"", pair))?;
+            match (position.trim().parse(), weight.trim().parse::<i64>()) {
+                (Ok(position), Ok(weight)) if weight >= 0 => Ok((position, weight)),
+                (Ok(_), Ok(_)) => Err(format!("Weight in \"{}\" must not be negative", pair)),
+                _ => Err(format!("Invalid weight \"{}\"", pair)),
+            }
+        })
+        .collect()
+}
+
+pub fn run_cli(options: &[String]) -> Result<(), String> {
+    let args = Args::parse(options, &["model", "cap", "weights", "data", "breakdown"])?;
+
+    let mut model = parse_model(&args.get_or("model", "linear".to_string())?)?;
+    if let Some(weights) = args.get::<String>("weights")? {
+        model = Box::new(Weighted {
+            inner: model,
+            weights: parse_weights(&weights)?,
+        });
+    }
+    if let Some(cap) = args.get("cap")? {
+        model = Box::new(Capped { inner: model, cap });
+    }
+
+    let data = match args.get_or("data", "input".to_string())?.as_str() {
+        "sample" => Day7::sample_data(),
+        "input" => Day7::input_data(),
+        other => return Err(format!("Unknown data set \"{}\"", other)),
+    };
+    let alignment = align(&counterize(data), model.as_ref()).map_err(|error| error.to_string())?;
+
+    println!(
+        "Best position: {}, total fuel: {}",
+        alignment.position, alignment.total
+    );
+    if args.get_or("breakdown", false)? {
+        for crab in &alignment.breakdown {
+            println!("{:>6} x{:<3} {}", crab.position, crab.count, crab.fuel);
+        }
+    }
+
+    Ok(())
 }
 
 impl Solution for Day7 {
@@ -51,16 +370,122 @@ impl Solution for Day7 {
     }
 
     fn solution_impl_1(data: Vec<String>) -> Option<i64> {
-        let counts = counterize(data);
-        (*counts.keys().min().unwrap()..=*counts.keys().max().unwrap())
-            .map(|c| get_abs_delta_sum(&counts, c))
-            .min()
+        align(&counterize(data), &Linear)
+            .ok()
+            .map(|alignment| alignment.total)
     }
 
     fn solution_impl_2(data: Vec<String>) -> Option<i64> {
-        let counts = counterize(data);
-        (*counts.keys().min().unwrap()..=*counts.keys().max().unwrap())
-            .map(|c| get_abs_delta_triangle_sum(&counts, c))
-            .min()
+        align(&counterize(data), &Triangular)
+            .ok()
+            .map(|alignment| alignment.total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::random::XorShift;
+
+    // Deterministic pseudo-random crab positions in 0..range.
+    fn crabs(seed: u64, len: usize, range: u64) -> Counter<i64> {
+        let mut rng = XorShift::new(seed);
+        (0..len).map(|_| rng.below(range) as i64).collect()
+    }
+
+    fn brute_force_total<M: FuelCost>(counts: &Counter<i64>, model: &M) -> i64 {
+        let (min, max) = bounds(counts).unwrap();
+        let cost = |target| total_fuel(counts, model, target);
+        cost(brute_force(min, max, cost).unwrap().unwrap()).unwrap()
+    }
+
+    fn check_against_brute_force<M: FuelCost>(model: &M) {
+        for seed in 1..=20 {
+            for (len, range) in [(1, 10), (2, 10), (7, 20), (50, 100), (100, 300)] {
+                let counts = crabs(seed, len, range);
+                assert_eq!(
+                    align(&counts, model).unwrap().total,
+                    brute_force_total(&counts, model),
+                    "seed {}, {} crabs in 0..{}",
+                    seed,
+                    len,
+                    range
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn linear_matches_brute_force() {
+        check_against_brute_force(&Linear);
+    }
+
+    #[test]
+    fn triangular_matches_brute_force() {
+        check_against_brute_force(&Triangular);
+    }
+
+    #[test]
+    fn ternary_search_matches_brute_force() {
+        // Quadratic has no closed form, so it goes through the ternary search.
+        check_against_brute_force(&Quadratic);
+    }
+
+    #[test]
+    fn convexity_is_opt_in() {
+        assert!(Linear.is_convex() && Triangular.is_convex() && Quadratic.is_convex());
+
+        let capped = Capped {
+            inner: Box::new(Linear),
+            cap: 3,
+        };
+        assert!(!capped.is_convex());
+        check_against_brute_force(&capped);
+
+        let weighted = Weighted {
+            inner: Box::new(Triangular),
+            weights: HashMap::from([(1, 3), (4, 0)]),
+        };
+        assert!(weighted.is_convex());
+        check_against_brute_force(&weighted);
+    }
+
+    #[test]
+    fn custom_cost_overflow_is_reported() {
+        let model = parse_model("power:40").unwrap();
+        let counts = Counter::from_counts([(0, 1), (5, 1)]);
+        assert!(matches!(
+            align(&counts, model.as_ref()),
+            Err(AlignError::Overflow(_))
+        ));
+    }
+
+    #[test]
+    fn distance_overflow_is_reported() {
+        assert_eq!(distance(-5, 3), Ok(8));
+        assert_eq!(distance(i64::MIN + 1, i64::MAX).ok(), None);
+        assert_eq!(distance(-1, i64::MAX).ok(), None);
+
+        let counts = Counter::from_counts([(-1, 1), (i64::MAX, 2)]);
+        assert!(matches!(
+            align(&counts, &Linear),
+            Err(AlignError::Overflow(_))
+        ));
+    }
+
+    #[test]
+    fn weights_must_not_be_negative() {
+        assert_eq!(
+            parse_weights("1:2, 3:0"),
+            Ok(HashMap::from([(1, 2), (3, 0)]))
+        );
+        assert!(parse_weights("1:-2").is_err());
+        assert!(parse_weights("1").is_err());
+    }
+
+    #[test]
+    fn sample() {
+        assert_eq!(Day7::solution_impl_1(Day7::sample_data()), Some(37));
+        assert_eq!(Day7::solution_impl_2(Day7::sample_data()), Some(168));
     }
 }
@@ -1,223 +1,598 @@
-use std::collections::{BTreeSet, HashMap};
-
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::ops::{BitAnd, BitOr, Sub};
+use std::str::FromStr;
+
+use crate::utils::cli::Args;
+use crate::utils::parse::{self, literal, separated, word, Cursor, Parser};
+use crate::utils::random::XorShift;
 use crate::utils::Solution;
 
 pub struct Day8;
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct ParseSegmentsError {
+    pub text: String,
+}
+
+impl Display for ParseSegmentsError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "Invalid segments \"{}\", expected letters a to h",
+            self.text
+        )
+    }
+}
+
+impl std::error::Error for ParseSegmentsError {}
+
+/// Set of segments or wires, named by the letters a to h. Bit `i` stands for the `i`th letter,
+/// so "acf" is 0b100101.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct Segments(u8);
+
+impl Segments {
+    fn empty() -> Self {
+        Segments(0)
+    }
+
+    fn single(letter: char) -> Option<Self> {
+        match letter {
+            'a'..='h' => Some(Segments(1 << (letter as u8 - b'a'))),
+            _ => None,
+        }
+    }
+
+    fn len(self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    fn contains(self, letter: char) -> bool {
+        Segments::single(letter).is_some_and(|single| self.is_superset(single))
+    }
+
+    fn is_superset(self, other: Segments) -> bool {
+        self & other == other
+    }
+
+    fn insert(&mut self, letter: char) {
+        *self = *self | Segments::single(letter).unwrap();
+    }
+
+    fn iter(self) -> impl Iterator<Item = char> {
+        ('a'..='h').filter(move |&letter| self.contains(letter))
+    }
+}
+
+impl FromStr for Segments {
+    type Err = ParseSegmentsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.chars()
+            .map(Segments::single)
+            .try_fold(Segments::empty(), |res, single| Some(res | single?))
+            .ok_or_else(|| ParseSegmentsError {
+                text: s.to_string(),
+            })
+    }
+}
+
+impl FromIterator<char> for Segments {
+    fn from_iter<I: IntoIterator<Item = char>>(iter: I) -> Self {
+        let mut res = Segments::empty();
+        for letter in iter {
+            res.insert(letter);
+        }
+        res
+    }
+}
+
+impl BitOr for Segments {
+    type Output = Segments;
+
+    fn bitor(self, rhs: Segments) -> Segments {
+        Segments(self.0 | rhs.0)
+    }
+}
+
+impl BitAnd for Segments {
+    type Output = Segments;
+
+    fn bitand(self, rhs: Segments) -> Segments {
+        Segments(self.0 & rhs.0)
+    }
+}
+
+impl Sub for Segments {
+    type Output = Segments;
+
+    fn sub(self, rhs: Segments) -> Segments {
+        Segments(self.0 & !rhs.0)
+    }
+}
+
+impl Display for Segments {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.iter().collect::<String>())
+    }
+}
+
+/// Draws each display in the standard layout, side by side.
+///
+///  aaaa
+/// b    c
+/// b    c
+///  dddd
+/// e    f
+/// e    f
+///  gggg
+fn render(displays: &[Segments]) -> String {
+    let horizontal = |display: Segments, letter| {
+        if display.contains(letter) {
+            " ---- "
+        } else {
+            "      "
+        }
+    };
+    let vertical =
+        |display: Segments, left, right| match (display.contains(left), display.contains(right)) {
+            (true, true) => "|    |",
+            (true, false) => "|     ",
+            (false, true) => "     |",
+            (false, false) => "      ",
+        };
+
+    let rows: [&dyn Fn(Segments) -> &'static str; 7] = [
+        &|display| horizontal(display, 'a'),
+        &|display| vertical(display, 'b', 'c'),
+        &|display| vertical(display, 'b', 'c'),
+        &|display| horizontal(display, 'd'),
+        &|display| vertical(display, 'e', 'f'),
+        &|display| vertical(display, 'e', 'f'),
+        &|display| horizontal(display, 'g'),
+    ];
+
+    rows.iter()
+        .map(|row| {
+            displays
+                .iter()
+                .map(|&display| row(display))
+                .collect::<Vec<&str>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+#[derive(Debug, PartialEq, Eq)]
 struct Splits {
-    pub inputs: Vec<String>,
-    pub outputs: Vec<String>,
+    pub inputs: Vec<Segments>,
+    pub outputs: Vec<Segments>,
+}
+
+fn segments<'a>() -> impl Parser<'a, Segments> {
+    move |cursor: &mut Cursor<'a>| {
+        let start = *cursor;
+        word()
+            .parse(cursor)?
+            .parse::<Segments>()
+            .map_err(|_| start.error("segments a to h"))
+    }
 }
 
-fn to_set(s: &str) -> BTreeSet<char> {
-    s.chars().collect()
+fn patterns<'a>() -> impl Parser<'a, Vec<Segments>> {
+    separated(segments(), literal(" "))
 }
 
 impl Splits {
-    fn from_input(s: &str) -> Self {
-        let mut splits = s.split(" | ").map(str::to_string).collect::<Vec<String>>();
-        assert_eq!(splits.len(), 2, "Input has wrong number of splits.");
-
-        let outputs = splits
-            .pop()
-            .unwrap()
-            .split(' ')
-            .map(str::to_string)
-            .collect::<Vec<String>>();
-
-        let inputs = splits
-            .pop()
-            .unwrap()
-            .split(' ')
-            .map(str::to_string)
-            .collect::<Vec<String>>();
-
-        assert_eq!(inputs.len(), 10, "Input not size 10.");
-
-        Splits { inputs, outputs }
-    }
-
-    fn derive_mappings(&self) -> HashMap<BTreeSet<char>, i64> {
-        // Full decode
-        //
-        //  aaaa
-        // b    c
-        // b    c
-        //  dddd
-        // e    f
-        // e    f
-        //  gggg
-        //
-        // Easy numbers:
-        //   - 1: Always length 2.
-        //   - 4: Always length 4.
-        //   - 7: Always length 3.
-        //   - 8: Always length 7.
-        //
-        // Inferences:
-        //   - Length 6 items:
-        //     - 8 - 7: Gives bdeg. In length 6s, only 6 has this ==> derives 6.
-        //     - 4 - 1: Gives bd. In leftover 6s, only 9 has this ==> derives 9.
-        //     - By default, only remaining 6 is 0 ==> derives 0.
-        //   - Length 5 items:
-        //     - Using bd again, in length 5s, only 5 has this ==> derives 5.
-        //     - In leftover 5s, only 3 contains 1 ==> derives 3.
-        //     - Only left is 2 ==> derives 2.
-        let mut res: HashMap<BTreeSet<char>, i64> = HashMap::new();
-        let mut res_rev: HashMap<i64, BTreeSet<char>> = HashMap::new();
-
-        // Get easy ones.
-        for segments in &self.inputs {
-            match segments.len() {
-                2 => {
-                    res.insert(to_set(segments), 1);
-                    res_rev.insert(1, to_set(segments));
-                }
-                4 => {
-                    res.insert(to_set(segments), 4);
-                    res_rev.insert(4, to_set(segments));
-                }
-                3 => {
-                    res.insert(to_set(segments), 7);
-                    res_rev.insert(7, to_set(segments));
-                }
-                7 => {
-                    res.insert(to_set(segments), 8);
-                    res_rev.insert(8, to_set(segments));
-                }
-                _ => (),
-            }
+    fn from_input(s: &str) -> Result<Self, String> {
+        // Split on the bar first, since a space separator followed by '|' isn't a pattern.
+        let (inputs, outputs) = s
+            .split_once(" | ")
+            .ok_or_else(|| format!("Expected \" | \" in \"{}\"", s))?;
+        let inputs =
+            parse::parse_all(patterns(), inputs).map_err(|error| format!("Inputs, {}", error))?;
+        let outputs =
+            parse::parse_all(patterns(), outputs).map_err(|error| format!("Outputs, {}", error))?;
+
+        if inputs.len() != 10 {
+            return Err(format!(
+                "Expected 10 input patterns but found {}",
+                inputs.len()
+            ));
         }
 
-        for num in [1, 4, 7, 8] {
-            assert!(
-                res_rev.contains_key(&num),
-                "Failed to acquire easy category value {}.",
-                num
-            );
+        Ok(Splits { inputs, outputs })
+    }
+}
+
+/// Which segments light up for each symbol a display can show.
+#[derive(Debug)]
+struct SegmentTable {
+    // Mapping of {segments => symbol}.
+    symbols: BTreeMap<Segments, i64>,
+}
+
+impl SegmentTable {
+    fn new(symbols: &[(i64, &str)]) -> Self {
+        SegmentTable {
+            symbols: symbols
+                .iter()
+                .map(|(symbol, segments)| (segments.parse().unwrap(), *symbol))
+                .collect(),
         }
+    }
+
+    /// The usual seven-segment digits, laid out as in `render`.
+    fn standard() -> Self {
+        SegmentTable::new(&[
+            (0, "abcefg"),
+            (1, "cf"),
+            (2, "acdeg"),
+            (3, "acdfg"),
+            (4, "bcdf"),
+            (5, "abdfg"),
+            (6, "abdefg"),
+            (7, "acf"),
+            (8, "abcdefg"),
+            (9, "abcdfg"),
+        ])
+    }
+
+    fn segments(&self) -> Segments {
+        self.symbols
+            .keys()
+            .fold(Segments::empty(), |res, &segments| res | segments)
+    }
 
-        // Infer 6.
-        let bdeg = res_rev
-            .get(&8)
-            .unwrap()
-            .difference(res_rev.get(&7).unwrap())
+    fn with_len(&self, len: usize) -> impl Iterator<Item = Segments> + '_ {
+        self.symbols
+            .keys()
             .cloned()
-            .collect();
+            .filter(move |segments| segments.len() == len)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum DecodeError {
+    /// No wiring is consistent with the patterns.
+    Impossible,
+    /// More than one wiring is consistent with the patterns.
+    Ambiguous,
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            DecodeError::Impossible => write!(f, "No wiring matches the observed patterns"),
+            DecodeError::Ambiguous => write!(f, "Several wirings match the observed patterns"),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// A recovered wiring for one display.
+#[derive(Debug)]
+struct Decoding<'t> {
+    table: &'t SegmentTable,
+    // Mapping of {wire => segment}.
+    wiring: BTreeMap<char, char>,
+}
+
+impl Decoding<'_> {
+    /// The segments actually lit by a pattern of wires.
+    fn translate(&self, pattern: Segments) -> Option<Segments> {
+        pattern
+            .iter()
+            .map(|wire| self.wiring.get(&wire).cloned())
+            .collect()
+    }
+
+    fn decode(&self, pattern: Segments) -> Option<i64> {
+        self.table.symbols.get(&self.translate(pattern)?).cloned()
+    }
+}
+
+/// Recovers the wire-to-segment permutation that turns every pattern into a symbol of `table`.
+///
+/// Candidate segments for each wire are first narrowed by propagation: a pattern of length n
+/// can only light segments used by some n-segment symbol, and segments shared by all of those
+/// symbols can't come from wires outside the pattern. Whatever is left is settled by
+/// backtracking, which also detects a second consistent wiring.
+fn solve_wiring<'t>(
+    table: &'t SegmentTable,
+    patterns: &[Segments],
+) -> Result<Decoding<'t>, DecodeError> {
+    let segments = table.segments();
+    let wires = patterns
+        .iter()
+        .fold(segments, |res, &pattern| res | pattern);
+    if wires.len() != segments.len() {
+        return Err(DecodeError::Impossible);
+    }
 
-        for segments in &self.inputs {
-            if segments.len() != 6 {
-                continue;
+    // Mapping of {wire => segments it could drive}.
+    let mut candidates: BTreeMap<char, Segments> =
+        wires.iter().map(|wire| (wire, segments)).collect();
+
+    let mut changed = true;
+    while changed {
+        let before = candidates.clone();
+
+        for &pattern in patterns {
+            let options = table.with_len(pattern.len()).collect::<Vec<Segments>>();
+            let allowed = options
+                .iter()
+                .fold(Segments::empty(), |res, &option| res | option);
+            let required = options.iter().fold(segments, |res, &option| res & option);
+
+            for (&wire, possible) in candidates.iter_mut() {
+                if pattern.contains(wire) {
+                    *possible = *possible & allowed;
+                } else {
+                    *possible = *possible - required;
+                }
             }
+        }
 
-            if to_set(segments).is_superset(&bdeg) {
-                res.insert(to_set(segments), 6);
-                res_rev.insert(6, to_set(segments));
-                break;
+        // A wire with a single option claims that segment from every other wire.
+        let settled: Vec<(char, Segments)> = candidates
+            .iter()
+            .filter(|(_, possible)| possible.len() == 1)
+            .map(|(&wire, &possible)| (wire, possible))
+            .collect();
+        for (wire, segment) in settled {
+            for (&other, possible) in candidates.iter_mut() {
+                if other != wire {
+                    *possible = *possible - segment;
+                }
             }
         }
 
-        assert!(
-            res_rev.contains_key(&6),
-            "Failed to acquire derived category value 6.",
-        );
+        if candidates.values().any(|possible| possible.is_empty()) {
+            return Err(DecodeError::Impossible);
+        }
+        changed = candidates != before;
+    }
 
-        // Infer 9.
-        let bd = res_rev
-            .get(&4)
-            .unwrap()
-            .difference(res_rev.get(&1).unwrap())
-            .cloned()
+    let mut found = vec![];
+    search_wiring(
+        table,
+        patterns,
+        &candidates,
+        &mut BTreeMap::new(),
+        &mut found,
+    );
+
+    match found.len() {
+        0 => Err(DecodeError::Impossible),
+        1 => Ok(Decoding {
+            table,
+            wiring: found.pop().unwrap(),
+        }),
+        _ => Err(DecodeError::Ambiguous),
+    }
+}
+
+/// Depth-first assignment of the undecided wires, stopping once two wirings are found.
+fn search_wiring(
+    table: &SegmentTable,
+    patterns: &[Segments],
+    candidates: &BTreeMap<char, Segments>,
+    wiring: &mut BTreeMap<char, char>,
+    found: &mut Vec<BTreeMap<char, char>>,
+) {
+    if found.len() > 1 {
+        return;
+    }
+
+    // Every pattern must still fit inside some symbol of its length.
+    let consistent = patterns.iter().all(|pattern| {
+        let lit: Segments = pattern
+            .iter()
+            .filter_map(|wire| wiring.get(&wire).cloned())
             .collect();
+        table
+            .with_len(pattern.len())
+            .any(|option| option.is_superset(lit))
+    });
+    if !consistent {
+        return;
+    }
 
-        for segments in &self.inputs {
-            if segments.len() != 6 || res.contains_key(&to_set(segments)) {
-                continue;
-            }
+    let next = candidates
+        .iter()
+        .filter(|(wire, _)| !wiring.contains_key(wire))
+        .min_by_key(|(_, possible)| possible.len());
+    let (&wire, possible) = match next {
+        Some(next) => next,
+        None => {
+            found.push(wiring.clone());
+            return;
+        }
+    };
 
-            if to_set(segments).is_superset(&bd) {
-                res.insert(to_set(segments), 9);
-                res_rev.insert(9, to_set(segments));
-                break;
-            }
+    for segment in possible.iter() {
+        if wiring.values().any(|&used| used == segment) {
+            continue;
         }
 
-        assert!(
-            res_rev.contains_key(&9),
-            "Failed to acquire derived category value 9.",
-        );
+        wiring.insert(wire, segment);
+        search_wiring(table, patterns, candidates, wiring, found);
+        wiring.remove(&wire);
+    }
+}
 
-        // Infer 0.
-        for segments in &self.inputs {
-            if segments.len() != 6 || res.contains_key(&to_set(segments)) {
-                continue;
-            }
+/// Advances `perm` to the next lexicographic permutation, returning false after the last one.
+fn next_permutation(perm: &mut [usize]) -> bool {
+    let pivot = match perm.windows(2).rposition(|pair| pair[0] < pair[1]) {
+        Some(pivot) => pivot,
+        None => return false,
+    };
+    let swap = perm.iter().rposition(|&val| val > perm[pivot]).unwrap();
+    perm.swap(pivot, swap);
+    perm[pivot + 1..].reverse();
+    true
+}
 
-            res.insert(to_set(segments), 0);
-            res_rev.insert(0, to_set(segments));
+/// Tries every wire permutation, keeping those that turn each pattern into a symbol. Far slower
+/// than `solve_wiring` but independent of its reasoning, so it serves as a reference.
+fn brute_force_wiring<'t>(
+    table: &'t SegmentTable,
+    patterns: &[Segments],
+) -> Result<Decoding<'t>, DecodeError> {
+    let segments: Vec<char> = table.segments().iter().collect();
+    let wires = patterns
+        .iter()
+        .fold(table.segments(), |res, &pattern| res | pattern);
+    if wires.len() != segments.len() {
+        return Err(DecodeError::Impossible);
+    }
+
+    // perm[i] is the index of the segment driven by the ith wire.
+    let mut perm: Vec<usize> = (0..segments.len()).collect();
+    let mut found = vec![];
+    loop {
+        let rewire = |pattern: Segments| {
+            (0..segments.len())
+                .filter(|&wire| pattern.contains(segments[wire]))
+                .map(|wire| segments[perm[wire]])
+                .collect::<Segments>()
+        };
+        if patterns
+            .iter()
+            .all(|&pattern| table.symbols.contains_key(&rewire(pattern)))
+        {
+            found.push(perm.clone());
+        }
+
+        if found.len() > 1 || !next_permutation(&mut perm) {
             break;
         }
+    }
 
-        assert!(
-            res_rev.contains_key(&0),
-            "Failed to acquire derived category value 0.",
-        );
+    match found.len() {
+        0 => Err(DecodeError::Impossible),
+        1 => Ok(Decoding {
+            table,
+            wiring: found[0]
+                .iter()
+                .enumerate()
+                .map(|(wire, &segment)| (segments[wire], segments[segment]))
+                .collect(),
+        }),
+        _ => Err(DecodeError::Ambiguous),
+    }
+}
 
-        // Infer 5.
-        for segments in &self.inputs {
-            if segments.len() != 5 {
-                continue;
-            }
+/// Scrambles displays reproducibly from a seed.
+struct Scrambler {
+    rng: XorShift,
+}
 
-            if to_set(segments).is_superset(&bd) {
-                res.insert(to_set(segments), 5);
-                res_rev.insert(5, to_set(segments));
-                break;
-            }
+impl Scrambler {
+    fn new(seed: u64) -> Self {
+        Scrambler {
+            rng: XorShift::new(seed),
         }
+    }
 
-        assert!(
-            res_rev.contains_key(&5),
-            "Failed to acquire derived category value 5.",
-        );
+    /// Every symbol of `table` as seen through a random wiring, in random order, together
+    /// with that wiring.
+    fn display(&mut self, table: &SegmentTable) -> (Vec<Segments>, BTreeMap<char, char>) {
+        let segments: Vec<char> = table.segments().iter().collect();
+        let mut shuffled = segments.clone();
+        self.rng.shuffle(&mut shuffled);
+
+        // Mapping of {segment => wire}.
+        let wire_for: BTreeMap<char, char> = segments.iter().cloned().zip(shuffled).collect();
+        let mut patterns: Vec<Segments> = table
+            .symbols
+            .keys()
+            .map(|symbol| symbol.iter().map(|seg| wire_for[&seg]).collect())
+            .collect();
+        self.rng.shuffle(&mut patterns);
 
-        // Infer 3.
-        for segments in &self.inputs {
-            if segments.len() != 5 || res.contains_key(&to_set(segments)) {
-                continue;
-            }
+        let wiring = wire_for
+            .into_iter()
+            .map(|(seg, wire)| (wire, seg))
+            .collect();
+        (patterns, wiring)
+    }
+}
 
-            if to_set(segments).is_superset(res_rev.get(&1).unwrap()) {
-                res.insert(to_set(segments), 3);
-                res_rev.insert(3, to_set(segments));
-                break;
-            }
+/// Checks both decoders against each other on `rounds` randomly scrambled displays, returning
+/// the first disagreement.
+fn differential_check(table: &SegmentTable, seed: u64, rounds: usize) -> Result<(), String> {
+    let mut scrambler = Scrambler::new(seed);
+    for round in 0..rounds {
+        let (patterns, expected) = scrambler.display(table);
+        let fast = solve_wiring(table, &patterns).map(|decoding| decoding.wiring);
+        let slow = brute_force_wiring(table, &patterns).map(|decoding| decoding.wiring);
+        if fast != slow || fast.as_ref() != Ok(&expected) {
+            return Err(format!(
+                "Round {}: {} decoded as {:?} by the solver and {:?} by brute force",
+                round,
+                patterns
+                    .iter()
+                    .map(Segments::to_string)
+                    .collect::<Vec<String>>()
+                    .join(" "),
+                fast,
+                slow
+            ));
         }
+    }
 
-        assert!(
-            res_rev.contains_key(&3),
-            "Failed to acquire derived category value 3.",
-        );
+    Ok(())
+}
 
-        // Infer 2.
-        for segments in &self.inputs {
-            if segments.len() != 5 || res.contains_key(&to_set(segments)) {
-                continue;
-            }
+/// Prints each line's output digits as they would appear on a working display.
+fn render_decoded(data: Vec<String>) -> Result<(), String> {
+    let table = SegmentTable::standard();
+    for (idx, line) in data.iter().enumerate() {
+        let splits =
+            Splits::from_input(line).map_err(|error| format!("Line {}: {}", idx + 1, error))?;
+        let decoding = solve_wiring(&table, &splits.inputs)
+            .map_err(|error| format!("Line {}: {}", idx + 1, error))?;
+        let lit = splits
+            .outputs
+            .iter()
+            .map(|&output| decoding.translate(output))
+            .collect::<Option<Vec<Segments>>>()
+            .ok_or_else(|| format!("Line {}: output uses an unknown wire", idx + 1))?;
 
-            res.insert(to_set(segments), 2);
-            res_rev.insert(2, to_set(segments));
-            break;
-        }
+        println!("{}", line);
+        println!("{}\n", render(&lit));
+    }
 
-        assert!(
-            res_rev.contains_key(&3),
-            "Failed to acquire derived category value 2.",
-        );
+    Ok(())
+}
 
-        res
+pub fn run_cli(options: &[String]) -> Result<(), String> {
+    let args = Args::parse(options, &["cross-check", "seed", "render"])?;
+
+    if let Some(rounds) = args.get("cross-check")? {
+        differential_check(&SegmentTable::standard(), args.get_or("seed", 1)?, rounds)?;
+        println!("Decoders agree on {} scrambled displays", rounds);
     }
+
+    match args.get::<String>("render")?.as_deref() {
+        Some("sample") => render_decoded(Day8::sample_data())?,
+        Some("input") => render_decoded(Day8::input_data())?,
+        Some(other) => return Err(format!("Unknown data set \"{}\"", other)),
+        None => (),
+    }
+
+    Ok(())
 }
 
 impl Solution for Day8 {
@@ -230,7 +605,8 @@ impl Solution for Day8 {
             .iter()
             .map(String::as_str)
             .map(Splits::from_input)
-            .collect::<Vec<Splits>>();
+            .collect::<Result<Vec<Splits>, String>>()
+            .ok()?;
 
         all_splits
             .iter()
@@ -250,15 +626,17 @@ impl Solution for Day8 {
             .iter()
             .map(String::as_str)
             .map(Splits::from_input)
-            .collect::<Vec<Splits>>();
+            .collect::<Result<Vec<Splits>, String>>()
+            .ok()?;
 
+        let table = SegmentTable::standard();
         let mut sum: i64 = 0;
         for splits in all_splits {
             let mut cur_sum: i64 = 0;
-            let mappings = splits.derive_mappings();
-            for output in &splits.outputs {
+            let decoding = solve_wiring(&table, &splits.inputs).ok()?;
+            for &output in &splits.outputs {
                 cur_sum *= 10;
-                cur_sum += mappings.get(&to_set(output.as_str())).unwrap();
+                cur_sum += decoding.decode(output)?;
             }
 
             sum += cur_sum;
@@ -267,3 +645,151 @@ impl Solution for Day8 {
         sum.into()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn patterns(text: &str) -> Vec<Segments> {
+        text.split_whitespace()
+            .map(|pattern| pattern.parse().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn decoders_agree_on_scrambled_displays() {
+        let table = SegmentTable::standard();
+        for seed in [1, 2, 42] {
+            assert_eq!(differential_check(&table, seed, 50), Ok(()));
+        }
+    }
+
+    #[test]
+    fn too_few_patterns_are_ambiguous() {
+        let table = SegmentTable::standard();
+        for text in ["abcdefg", "ab abcdefg", "ab abd abcdefg"] {
+            let patterns = patterns(text);
+            assert_eq!(
+                solve_wiring(&table, &patterns).err(),
+                Some(DecodeError::Ambiguous),
+                "{}",
+                text
+            );
+            assert_eq!(
+                brute_force_wiring(&table, &patterns).err(),
+                Some(DecodeError::Ambiguous),
+                "{}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn inconsistent_patterns_are_impossible() {
+        let table = SegmentTable::standard();
+        // Two different 2-segment patterns, but only 1 lights two segments.
+        for text in ["ab cd", "ab abcdefgh", "abcde abcdf abcdg abcef"] {
+            let patterns = patterns(text);
+            assert_eq!(
+                solve_wiring(&table, &patterns).err(),
+                Some(DecodeError::Impossible),
+                "{}",
+                text
+            );
+            assert_eq!(
+                brute_force_wiring(&table, &patterns).err(),
+                Some(DecodeError::Impossible),
+                "{}",
+                text
+            );
+        }
+    }
+
+    const LINE: &str =
+        "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb fcadb cdfeb cdbaf";
+
+    #[test]
+    fn segments_parse_and_combine() {
+        let acf = "acf".parse::<Segments>().unwrap();
+        assert_eq!(acf, Segments(0b100101));
+        assert_eq!(acf.to_string(), "acf");
+        assert_eq!("fca".parse(), Ok(acf));
+        assert_eq!("cfa".chars().collect::<Segments>(), acf);
+        assert_eq!("".parse(), Ok(Segments::empty()));
+        assert_eq!(
+            "abi".parse::<Segments>(),
+            Err(ParseSegmentsError {
+                text: "abi".to_string()
+            })
+        );
+
+        let cf = "cf".parse::<Segments>().unwrap();
+        let abc = "abc".parse::<Segments>().unwrap();
+        assert_eq!(acf.len(), 3);
+        assert!(acf.contains('f') && !acf.contains('b') && !acf.contains('z'));
+        assert!(acf.is_superset(cf) && !cf.is_superset(acf));
+        assert_eq!((acf - cf).to_string(), "a");
+        assert_eq!((acf & abc).to_string(), "ac");
+        assert_eq!((cf | abc).to_string(), "abcf");
+        assert!((cf - acf).is_empty());
+    }
+
+    #[test]
+    fn bad_lines_are_errors() {
+        assert_eq!(
+            Splits::from_input(LINE).map(|splits| splits.outputs.len()),
+            Ok(4)
+        );
+        assert_eq!(
+            Splits::from_input("ab cd"),
+            Err("Expected \" | \" in \"ab cd\"".to_string())
+        );
+        assert_eq!(
+            Splits::from_input("ab cd | ef"),
+            Err("Expected 10 input patterns but found 2".to_string())
+        );
+        assert_eq!(
+            Splits::from_input(&LINE.replace("dab", "daz")),
+            Err("Inputs, line 1, column 27: expected segments a to h, found \"daz\"".to_string())
+        );
+        assert!(Splits::from_input(&LINE.replace("cdbaf", "cdba1"))
+            .unwrap_err()
+            .starts_with("Outputs, "));
+        assert_eq!(
+            Day8::solution_impl_1(vec![LINE.replace("ab |", "xy |")]),
+            None
+        );
+        assert!(render_decoded(vec!["ab | cd".to_string()]).is_err());
+    }
+
+    #[test]
+    fn renders_decoded_outputs() {
+        let splits = Splits::from_input(LINE).unwrap();
+        let table = SegmentTable::standard();
+        let decoding = solve_wiring(&table, &splits.inputs).unwrap();
+        let lit = splits
+            .outputs
+            .iter()
+            .map(|&output| decoding.translate(output).unwrap())
+            .collect::<Vec<Segments>>();
+        assert_eq!(
+            render(&lit),
+            [
+                " ----    ----    ----    ----",
+                "|            |  |            |",
+                "|            |  |            |",
+                " ----    ----    ----    ----",
+                "     |       |       |       |",
+                "     |       |       |       |",
+                " ----    ----    ----    ----",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn sample() {
+        assert_eq!(Day8::solution_impl_1(Day8::sample_data()), Some(26));
+        assert_eq!(Day8::solution_impl_2(Day8::sample_data()), Some(61229));
+    }
+}
@@ -1,100 +1,9 @@
-use std::collections::BinaryHeap;
-use std::collections::HashSet;
-use std::fmt::{Display, Formatter};
-
+use crate::utils::grid::Grid;
+use crate::utils::search;
 use crate::utils::Solution;
 
 pub struct Day9;
 
-#[derive(Debug)]
-pub struct Grid {
-    pub data: Vec<Vec<i64>>,
-}
-
-impl Grid {
-    pub fn from_data(data: Vec<String>) -> Self {
-        Grid {
-            data: data
-                .into_iter()
-                .map(|s| {
-                    s.chars()
-                        .map(|c| str::parse::<i64>(&c.to_string()))
-                        .map(Result::unwrap)
-                        .collect::<Vec<i64>>()
-                })
-                .collect::<Vec<Vec<i64>>>(),
-        }
-    }
-
-    pub fn at(&self, row: i64, col: i64) -> Option<i64> {
-        if self.data.is_empty() {
-            return None;
-        }
-
-        if row < 0
-            || col < 0
-            || row as usize >= self.data.len()
-            || col as usize >= self.data[0].len()
-        {
-            None
-        } else {
-            Some(self.data[row as usize][col as usize])
-        }
-    }
-
-    pub fn at_mut(&mut self, row: i64, col: i64) -> Option<&mut i64> {
-        if self.data.is_empty() {
-            return None;
-        }
-
-        if row < 0
-            || col < 0
-            || row as usize >= self.data.len()
-            || col as usize >= self.data[0].len()
-        {
-            None
-        } else {
-            self.data
-                .get_mut(row as usize)
-                .unwrap()
-                .get_mut(col as usize)
-        }
-    }
-
-    fn dfs_count(&self, row: i64, col: i64, seen: &mut HashSet<(i64, i64)>) -> i64 {
-        if self.at(row, col).unwrap_or(9) == 9 || seen.contains(&(row, col)) {
-            return 0;
-        }
-
-        let mut count: i64 = 1;
-        seen.insert((row, col));
-        count += self.dfs_count(row - 1, col, seen);
-        count += self.dfs_count(row + 1, col, seen);
-        count += self.dfs_count(row, col - 1, seen);
-        count += self.dfs_count(row, col + 1, seen);
-
-        count
-    }
-}
-
-impl Display for Grid {
-    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
-        writeln!(
-            f,
-            "{}",
-            self.data
-                .iter()
-                .map(|vec_i64| vec_i64
-                    .iter()
-                    .map(i64::to_string)
-                    .collect::<Vec<String>>()
-                    .join(" "))
-                .collect::<Vec<String>>()
-                .join("\n")
-        )
-    }
-}
-
 fn less_than(lhs: i64, rhs: Option<i64>) -> bool {
     match rhs {
         Some(val) => lhs < val,
@@ -127,32 +36,15 @@ impl Solution for Day9 {
     }
 
     fn solution_impl_2(data: Vec<String>) -> Option<i64> {
-        let mut seen: HashSet<(i64, i64)> = HashSet::new();
-        let mut largest: BinaryHeap<i64> = BinaryHeap::with_capacity(4);
         let grid = Grid::from_data(data);
 
-        // Any DFS/BFS will do, just separate by whether the number is 9.
-        for row_idx in 0..grid.data.len() as i64 {
-            for col_idx in 0..grid.data[0].len() as i64 {
-                let count = grid.dfs_count(row_idx, col_idx, &mut seen);
-                largest.push(count);
-
-                if largest.len() == 4 {
-                    let mut largest_next: BinaryHeap<i64> = BinaryHeap::with_capacity(4);
-                    for _ in 0..3 {
-                        largest_next.push(largest.pop().unwrap());
-                    }
-
-                    largest = largest_next;
-                }
-            }
-        }
-
-        let mut product: i64 = 1;
-        for _ in 0..largest.len() {
-            product *= largest.pop().unwrap();
-        }
+        // Basins are separated by 9s, so each connected component of non-9s is one basin.
+        let basins = search::connected_components(&grid, grid.positions(), |(row, col)| {
+            grid.at(row, col).is_some_and(|val| val != 9)
+        });
 
-        Some(product)
+        let mut sizes = basins.sizes();
+        sizes.sort_unstable_by(|lhs, rhs| rhs.cmp(lhs));
+        Some(sizes.iter().take(3).product::<usize>() as i64)
     }
 }
@@ -18,7 +18,41 @@ pub fn run_latest() {
     day11::Day11::run();
 }
 
-#[allow(dead_code)]
+/// Runs the day named by the first argument, passing any further arguments to days which
+/// accept options.
+pub fn run_from_args(args: &[String]) -> Result<(), String> {
+    let (day, options) = args.split_first().ok_or("No day given")?;
+    if !options.is_empty() {
+        return match day.as_str() {
+            "4" => day4::run_cli(options),
+            "6" => day6::run_cli(options),
+            "7" => day7::run_cli(options),
+            "8" => day8::run_cli(options),
+            "10" => day10::run_cli(options),
+            _ => Err(format!("Day {} does not take any options", day)),
+        };
+    }
+
+    match day.as_str() {
+        "all" => run_all(),
+        "latest" => run_latest(),
+        "1" => day1::Day1::run(),
+        "2" => day2::Day2::run(),
+        "3" => day3::Day3::run(),
+        "4" => day4::Day4::run(),
+        "5" => day5::Day5::run(),
+        "6" => day6::Day6::run(),
+        "7" => day7::Day7::run(),
+        "8" => day8::Day8::run(),
+        "9" => day9::Day9::run(),
+        "10" => day10::Day10::run(),
+        "11" => day11::Day11::run(),
+        _ => return Err(format!("Unknown day \"{}\"", day)),
+    }
+
+    Ok(())
+}
+
 pub fn run_all() {
     day1::Day1::run();
     day2::Day2::run();
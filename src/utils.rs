@@ -1,18 +1,35 @@
+pub mod automaton;
+pub mod bigint;
+pub mod checked;
+pub mod cli;
+pub mod counter;
+pub mod grid;
+pub mod matrix;
+pub mod parse;
+pub mod point;
+pub mod random;
+pub mod search;
+pub mod sparse_grid;
+pub mod window;
+
 use std::fmt::{Debug, Display, Formatter};
+use std::ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign};
 use std::str::FromStr;
 
 #[derive(Copy, Clone, Debug)]
 enum SolutionMode {
-    SAMPLE,
-    INPUT,
+    Sample,
+    Input,
 }
 
 #[derive(Copy, Clone, Debug)]
 pub struct Part(i32);
 
-enum Colour {
-    RESET,
-    BOLD,
+pub enum Colour {
+    Reset,
+    Bold,
+    Red,
+    Blue,
 }
 
 impl Colour {
@@ -20,8 +37,10 @@ impl Colour {
         #[cfg(unix)]
         {
             match self {
-                Colour::RESET => "\x1b[0m",
-                Colour::BOLD => "\x1b[1m",
+                Colour::Reset => "\x1b[0m",
+                Colour::Bold => "\x1b[1m",
+                Colour::Red => "\x1b[31m",
+                Colour::Blue => "\x1b[34m",
             }
         }
 
@@ -46,13 +65,13 @@ impl From<std::path::PathBuf> for ReadablePathBuf {
 }
 
 impl SolutionMode {
-    fn to_path(&self, problem_number: i32) -> ReadablePathBuf {
+    fn to_path(self, problem_number: i32) -> ReadablePathBuf {
         let day_str = format!("day{problem_number}");
         let day_root = base_path().join(&day_str);
 
         match self {
-            SolutionMode::SAMPLE => day_root.join("sample.txt").into(),
-            SolutionMode::INPUT => day_root.join("input.txt").into(),
+            SolutionMode::Sample => day_root.join("sample.txt").into(),
+            SolutionMode::Input => day_root.join("input.txt").into(),
         }
     }
 }
@@ -94,28 +113,32 @@ pub trait Solution {
         }
     }
 
+    fn sample_data() -> Vec<String> {
+        SolutionMode::Sample
+            .to_path(Self::day_number())
+            .read_part_as_vec()
+    }
+
+    fn input_data() -> Vec<String> {
+        SolutionMode::Input
+            .to_path(Self::day_number())
+            .read_part_as_vec()
+    }
+
     fn sample(part: Part) -> Option<i64> {
-        Self::get_func(part)(
-            SolutionMode::SAMPLE
-                .to_path(Self::day_number())
-                .read_part_as_vec(),
-        )
+        Self::get_func(part)(Self::sample_data())
     }
 
     fn input(part: Part) -> Option<i64> {
-        Self::get_func(part)(
-            SolutionMode::INPUT
-                .to_path(Self::day_number())
-                .read_part_as_vec(),
-        )
+        Self::get_func(part)(Self::input_data())
     }
 
     fn run() {
         println!(
             "{}==> Day {}{}",
-            Colour::BOLD.to_string(),
+            Colour::Bold.to_string(),
             Self::day_number(),
-            Colour::RESET.to_string()
+            Colour::Reset.to_string()
         );
 
         let part1_sample = Self::sample(Part(1));
@@ -137,7 +160,8 @@ pub trait Solution {
     }
 }
 
-#[derive(PartialEq, Eq, Hash, Debug)]
+/// Ordered by x and then y.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
 pub struct XY<T: Eq> {
     pub x: T,
     pub y: T,
@@ -147,26 +171,346 @@ impl<T: Eq> XY<T> {
     pub fn new(x: T, y: T) -> Self {
         XY { x, y }
     }
+
+    /// Converts to another integer type, failing if either component does not fit.
+    #[allow(dead_code)]
+    pub fn try_cast<U: Eq + TryFrom<T>>(self) -> Result<XY<U>, U::Error> {
+        Ok(XY {
+            x: U::try_from(self.x)?,
+            y: U::try_from(self.y)?,
+        })
+    }
+}
+
+impl<T: Eq + Add<Output = T>> Add for XY<T> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        XY::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl<T: Eq + AddAssign> AddAssign for XY<T> {
+    fn add_assign(&mut self, rhs: Self) {
+        self.x += rhs.x;
+        self.y += rhs.y;
+    }
+}
+
+impl<T: Eq + Sub<Output = T>> Sub for XY<T> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        XY::new(self.x - rhs.x, self.y - rhs.y)
+    }
 }
 
+impl<T: Eq + SubAssign> SubAssign for XY<T> {
+    fn sub_assign(&mut self, rhs: Self) {
+        self.x -= rhs.x;
+        self.y -= rhs.y;
+    }
+}
+
+impl<T: Eq + Copy + Mul<Output = T>> Mul<T> for XY<T> {
+    type Output = Self;
+
+    fn mul(self, rhs: T) -> Self {
+        XY::new(self.x * rhs, self.y * rhs)
+    }
+}
+
+impl<T: Eq + Copy + MulAssign> MulAssign<T> for XY<T> {
+    fn mul_assign(&mut self, rhs: T) {
+        self.x *= rhs;
+        self.y *= rhs;
+    }
+}
+
+impl<T: Eq + Neg<Output = T>> Neg for XY<T> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        XY::new(-self.x, -self.y)
+    }
+}
+
+macro_rules! impl_xy_distances {
+    ($($t:ty),*) => {
+        $(
+            #[allow(dead_code)]
+            impl XY<$t> {
+                /// `None` if the distance does not fit in the component type.
+                pub fn manhattan(&self, other: &Self) -> Option<$t> {
+                    let dist = self.x.abs_diff(other.x).checked_add(self.y.abs_diff(other.y))?;
+                    <$t>::try_from(dist).ok()
+                }
+
+                /// `None` if the distance does not fit in the component type.
+                pub fn chebyshev(&self, other: &Self) -> Option<$t> {
+                    <$t>::try_from(self.x.abs_diff(other.x).max(self.y.abs_diff(other.y))).ok()
+                }
+            }
+        )*
+    };
+}
+
+impl_xy_distances!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+macro_rules! impl_xy_signum {
+    ($($t:ty),*) => {
+        $(
+            #[allow(dead_code)]
+            impl XY<$t> {
+                /// Component-wise signum, i.e. the unit step (including diagonals) that moves
+                /// towards this offset.
+                pub fn signum(&self) -> Self {
+                    XY::new(self.x.signum(), self.y.signum())
+                }
+            }
+        )*
+    };
+}
+
+impl_xy_signum!(i8, i16, i32, i64, i128, isize);
+
 impl<T: Display + Eq> Display for XY<T> {
     fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
         write!(f, "({}, {})", self.x, self.y)
     }
 }
 
-impl<T: FromStr + Copy + Eq> From<&str> for XY<T> {
-    fn from(s: &str) -> Self {
-        let splits = s
-            .split(',')
-            .map(str::trim)
-            .flat_map(str::parse::<T>)
-            .collect::<Vec<T>>();
+/// Delimiter between the components of a point, e.g. "1,2", "3x4", "5 6" or "7..8".
+#[allow(dead_code)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Separator {
+    Comma,
+    X,
+    Whitespace,
+    DotDot,
+    Custom(&'static str),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseComponentsError {
+    BadToken {
+        text: String,
+        token: String,
+        index: usize,
+    },
+    WrongCount {
+        text: String,
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl Display for ParseComponentsError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            ParseComponentsError::BadToken { text, token, index } => write!(
+                f,
+                "Cannot parse token {} (\"{}\") of \"{}\"",
+                index, token, text
+            ),
+            ParseComponentsError::WrongCount {
+                text,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Expected {} components but found {} in \"{}\"",
+                expected, found, text
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ParseComponentsError {}
+
+/// Splits exactly `count` components out of strings like "1,2" or "1, 2, 3". Surrounding
+/// whitespace on each token is ignored.
+pub fn parse_components<T: FromStr>(
+    s: &str,
+    count: usize,
+    separator: Separator,
+) -> Result<Vec<T>, ParseComponentsError> {
+    let tokens: Vec<&str> = match separator {
+        Separator::Comma => s.split(',').collect(),
+        Separator::X => s.split('x').collect(),
+        Separator::Whitespace => s.split_whitespace().collect(),
+        Separator::DotDot => s.split("..").collect(),
+        Separator::Custom(pattern) => s.split(pattern).collect(),
+    };
+
+    let res = tokens
+        .iter()
+        .map(|token| token.trim())
+        .enumerate()
+        .map(|(index, token)| {
+            token
+                .parse::<T>()
+                .map_err(|_| ParseComponentsError::BadToken {
+                    text: s.to_string(),
+                    token: token.to_string(),
+                    index,
+                })
+        })
+        .collect::<Result<Vec<T>, ParseComponentsError>>()?;
+
+    if res.len() != count {
+        return Err(ParseComponentsError::WrongCount {
+            text: s.to_string(),
+            expected: count,
+            found: res.len(),
+        });
+    }
+
+    Ok(res)
+}
 
-        assert_eq!(splits.len(), 2, "Cannot parse XY from \"{}\"", s);
-        match splits[..] {
-            [x, y] => XY { x, y },
+impl<T: FromStr + Copy + Eq> XY<T> {
+    pub fn parse_with(s: &str, separator: Separator) -> Result<Self, ParseComponentsError> {
+        match parse_components::<T>(s, 2, separator)?[..] {
+            [x, y] => Ok(XY { x, y }),
             _ => unreachable!(),
         }
     }
 }
+
+impl<T: FromStr + Copy + Eq> FromStr for XY<T> {
+    type Err = ParseComponentsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        XY::parse_with(s, Separator::Comma)
+    }
+}
+
+impl<T: FromStr + Copy + Eq> TryFrom<&str> for XY<T> {
+    type Error = ParseComponentsError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn arithmetic() {
+        let a = XY::new(3, -4);
+        let b = XY::new(-1, 2);
+        assert_eq!(a + b, XY::new(2, -2));
+        assert_eq!(a - b, XY::new(4, -6));
+        assert_eq!(a * 3, XY::new(9, -12));
+        assert_eq!(-a, XY::new(-3, 4));
+
+        let mut c = a;
+        c += b;
+        assert_eq!(c, a + b);
+        c -= b;
+        assert_eq!(c, a);
+        c *= -2;
+        assert_eq!(c, XY::new(-6, 8));
+
+        assert!(XY::new(1, 9) < XY::new(2, 0));
+        assert!(XY::new(1, 0) < XY::new(1, 1));
+    }
+
+    #[test]
+    fn distances_and_signum() {
+        let a = XY::new(1i64, -2);
+        let b = XY::new(-3i64, 5);
+        assert_eq!(a.manhattan(&b), Some(11));
+        assert_eq!(a.chebyshev(&b), Some(7));
+        assert_eq!((b - a).signum(), XY::new(-1, 1));
+        assert_eq!(XY::new(0i64, 7).signum(), XY::new(0, 1));
+
+        // The distances themselves overflow, not just intermediate differences.
+        let low = XY::new(i8::MIN, 0);
+        let high = XY::new(i8::MAX, 0);
+        assert_eq!(low.manhattan(&high), None);
+        assert_eq!(low.chebyshev(&high), None);
+        assert_eq!(XY::new(0i8, 0).manhattan(&XY::new(100, 100)), None);
+        assert_eq!(XY::new(0i8, 0).chebyshev(&XY::new(100, 100)), Some(100));
+        assert_eq!(XY::new(0u8, 255).manhattan(&XY::new(255, 0)), None);
+        assert_eq!(XY::new(0u8, 200).manhattan(&XY::new(55, 200)), Some(55));
+    }
+
+    #[test]
+    fn casts() {
+        assert_eq!(XY::new(3i64, 4).try_cast::<u8>(), Ok(XY::new(3u8, 4)));
+        assert!(XY::new(-1i64, 4).try_cast::<u8>().is_err());
+        assert!(XY::new(1i64, 256).try_cast::<u8>().is_err());
+    }
+
+    #[test]
+    fn parses_every_separator() {
+        assert_eq!("1,2".parse::<XY<i64>>(), Ok(XY::new(1, 2)));
+        assert_eq!(XY::<i64>::try_from(" -3 , 4 "), Ok(XY::new(-3, 4)));
+        assert_eq!(XY::parse_with("5x6", Separator::X), Ok(XY::new(5, 6)));
+        assert_eq!(
+            XY::parse_with("  7 \t 8 ", Separator::Whitespace),
+            Ok(XY::new(7, 8))
+        );
+        assert_eq!(
+            XY::parse_with("-9..10", Separator::DotDot),
+            Ok(XY::new(-9, 10))
+        );
+        assert_eq!(
+            XY::parse_with("11 -> 12", Separator::Custom("->")),
+            Ok(XY::new(11, 12))
+        );
+        assert_eq!(
+            parse_components::<u8>("1, 2, 3", 3, Separator::Comma),
+            Ok(vec![1, 2, 3])
+        );
+    }
+
+    #[test]
+    fn reports_the_bad_token() {
+        let err = parse_components::<i64>("1,x,2", 3, Separator::Comma).unwrap_err();
+        assert_eq!(
+            err,
+            ParseComponentsError::BadToken {
+                text: "1,x,2".to_string(),
+                token: "x".to_string(),
+                index: 1,
+            }
+        );
+        assert_eq!(err.to_string(), "Cannot parse token 1 (\"x\") of \"1,x,2\"");
+
+        // Empty tokens between separators are bad tokens too.
+        assert!(matches!(
+            "1,".parse::<XY<i64>>(),
+            Err(ParseComponentsError::BadToken { index: 1, .. })
+        ));
+        assert!(matches!(
+            XY::<u8>::parse_with("300x1", Separator::X),
+            Err(ParseComponentsError::BadToken { index: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn reports_the_wrong_count() {
+        let err = "1,2,3".parse::<XY<i64>>().unwrap_err();
+        assert_eq!(
+            err,
+            ParseComponentsError::WrongCount {
+                text: "1,2,3".to_string(),
+                expected: 2,
+                found: 3,
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "Expected 2 components but found 3 in \"1,2,3\""
+        );
+        assert!(matches!(
+            XY::<i64>::parse_with("4", Separator::Whitespace),
+            Err(ParseComponentsError::WrongCount { found: 1, .. })
+        ));
+    }
+}
@@ -0,0 +1,446 @@
+//! Shared driver for step-based simulations such as cellular automata.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+pub trait Automaton {
+    /// Whatever the rule reports about a single step, e.g. how many cells changed.
+    type Stats;
+
+    /// Advances the state by one generation.
+    fn step(&mut self) -> Self::Stats;
+}
+
+type Observer<'o, A> = Box<dyn FnMut(usize, &A, &<A as Automaton>::Stats) + 'o>;
+
+/// Owns an automaton and counts the generations it has been stepped through.
+pub struct Simulation<'o, A: Automaton> {
+    pub state: A,
+    pub generation: usize,
+    observer: Option<Observer<'o, A>>,
+}
+
+impl<'o, A: Automaton> Simulation<'o, A> {
+    pub fn new(state: A) -> Self {
+        Simulation {
+            state,
+            generation: 0,
+            observer: None,
+        }
+    }
+
+    /// Calls `observer(generation, state, stats)` after every step.
+    pub fn with_observer(mut self, observer: impl FnMut(usize, &A, &A::Stats) + 'o) -> Self {
+        self.observer = Some(Box::new(observer));
+        self
+    }
+
+    pub fn step(&mut self) -> A::Stats {
+        let stats = self.state.step();
+        self.generation += 1;
+        if let Some(observer) = self.observer.as_mut() {
+            observer(self.generation, &self.state, &stats);
+        }
+
+        stats
+    }
+
+    /// Runs for exactly `steps` generations.
+    pub fn run(&mut self, steps: usize) -> Vec<A::Stats> {
+        (0..steps).map(|_| self.step()).collect()
+    }
+
+    /// Steps until `done` holds for the state, checking before every step. The generation the
+    /// predicate first held at is left in `self.generation`.
+    pub fn run_until(&mut self, done: impl Fn(&A) -> bool) -> Vec<A::Stats> {
+        let mut res = vec![];
+        while !done(&self.state) {
+            res.push(self.step());
+        }
+
+        res
+    }
+
+    #[allow(dead_code)]
+    pub fn into_state(self) -> A {
+        self.state
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CycleAlgorithm {
+    /// Remembers every state seen. Fastest, but holds up to `start + length` states in memory.
+    Hashing,
+    /// Tortoise and hare. Constant memory.
+    Floyd,
+    /// Power-of-two teleporting tortoise. Constant memory, fewer steps than Floyd.
+    Brent,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cycle {
+    /// First generation that is part of the cycle.
+    pub start: usize,
+    pub length: usize,
+}
+
+/// Looks for a cycle that has fully repeated within `limit` generations of `initial`, i.e. one
+/// with `start + length <= limit`. Every algorithm gives the same answer for the same `limit`.
+/// Generations in the result are relative to `initial`.
+pub fn find_cycle<A: Automaton + Clone + Eq + Hash>(
+    initial: &A,
+    algorithm: CycleAlgorithm,
+    limit: usize,
+) -> Option<Cycle> {
+    match algorithm {
+        CycleAlgorithm::Hashing => find_cycle_hashing(initial, limit),
+        CycleAlgorithm::Floyd => find_cycle_floyd(initial, limit),
+        CycleAlgorithm::Brent => find_cycle_brent(initial, limit),
+    }
+}
+
+/// Never simulates past generation `limit`.
+pub fn find_cycle_hashing<A: Automaton + Clone + Eq + Hash>(
+    initial: &A,
+    limit: usize,
+) -> Option<Cycle> {
+    // Mapping of {state => generation it was first seen at}.
+    let mut seen: HashMap<A, usize> = HashMap::new();
+    let mut cur = initial.clone();
+    for generation in 0..=limit {
+        if let Some(&start) = seen.get(&cur) {
+            return Some(Cycle {
+                start,
+                length: generation - start,
+            });
+        }
+
+        if generation < limit {
+            seen.insert(cur.clone(), generation);
+            cur.step();
+        }
+    }
+
+    None
+}
+
+/// The hare runs up to `2 * limit` generations ahead while searching.
+pub fn find_cycle_floyd<A: Automaton + Clone + Eq>(initial: &A, limit: usize) -> Option<Cycle> {
+    // Hare moves twice as fast, so they meet somewhere inside the cycle. The tortoise meets it
+    // at the first multiple of the length that is at least the start, which is never further
+    // than `start + length`, so a cycle within `limit` is always caught in `limit` steps.
+    let mut tortoise = initial.clone();
+    let mut hare = initial.clone();
+    let mut steps = 0;
+    loop {
+        if steps == limit {
+            return None;
+        }
+
+        tortoise.step();
+        hare.step();
+        hare.step();
+        steps += 1;
+        if tortoise == hare {
+            break;
+        }
+    }
+
+    // Meeting point is a multiple of the cycle length ahead, so walking both at the same speed
+    // from the start and the meeting point lines them up at the cycle start.
+    let mut start = 0;
+    tortoise = initial.clone();
+    while tortoise != hare {
+        tortoise.step();
+        hare.step();
+        start += 1;
+    }
+
+    within(
+        Cycle {
+            start,
+            length: cycle_length(&tortoise),
+        },
+        limit,
+    )
+}
+
+/// The hare runs up to `4 * limit` generations ahead while searching.
+pub fn find_cycle_brent<A: Automaton + Clone + Eq>(initial: &A, limit: usize) -> Option<Cycle> {
+    // Tortoise jumps to the hare every power of two steps until the hare laps it.
+    let mut power = 1;
+    let mut length = 1;
+    let mut tortoise = initial.clone();
+    let mut tortoise_generation = 0;
+    let mut hare = initial.clone();
+    hare.step();
+    while tortoise != hare {
+        if power == length {
+            // The hare has just searched `power` generations past the tortoise. Once the
+            // tortoise is at or past the last possible start and `power` covers the longest
+            // possible length, any cycle within `limit` would have been caught.
+            if tortoise_generation + 1 >= limit {
+                return None;
+            }
+
+            tortoise = hare.clone();
+            tortoise_generation += power;
+            power *= 2;
+            length = 0;
+        }
+
+        hare.step();
+        length += 1;
+    }
+
+    // With the hare a full cycle ahead, the two first meet at the cycle start.
+    let mut start = 0;
+    tortoise = initial.clone();
+    hare = initial.clone();
+    for _ in 0..length {
+        hare.step();
+    }
+    while tortoise != hare {
+        tortoise.step();
+        hare.step();
+        start += 1;
+    }
+
+    within(Cycle { start, length }, limit)
+}
+
+// Floyd and Brent can catch cycles that only complete after `limit`, which `find_cycle` must not
+// report.
+fn within(cycle: Cycle, limit: usize) -> Option<Cycle> {
+    (cycle.start + cycle.length <= limit).then_some(cycle)
+}
+
+fn cycle_length<A: Automaton + Clone + Eq>(on_cycle: &A) -> usize {
+    let mut cur = on_cycle.clone();
+    cur.step();
+    let mut length = 1;
+    while cur != *on_cycle {
+        cur.step();
+        length += 1;
+    }
+
+    length
+}
+
+impl<'o, A: Automaton + Clone + Eq + Hash> Simulation<'o, A> {
+    /// Looks for a cycle starting from the current state, reporting absolute generations.
+    pub fn find_cycle(&self, algorithm: CycleAlgorithm, limit: usize) -> Option<Cycle> {
+        find_cycle(&self.state, algorithm, limit).map(|cycle| Cycle {
+            start: cycle.start + self.generation,
+            length: cycle.length,
+        })
+    }
+
+    /// Moves the state to generation `target`. If the state cycles within `limit` steps, only
+    /// enough steps to land on the equivalent point of the cycle are simulated; otherwise every
+    /// step is. Only cycles that complete by `target` are used, since simulating straight there
+    /// is cheaper than finding a later one. `Hashing` never simulates past `target` while
+    /// searching; `Floyd` and `Brent` run their hare a few times further ahead, as documented on
+    /// their search functions. The observer is not called for skipped or fast-forwarded steps.
+    #[allow(dead_code)]
+    pub fn fast_forward(
+        &mut self,
+        target: usize,
+        algorithm: CycleAlgorithm,
+        limit: usize,
+    ) -> Option<Cycle> {
+        assert!(
+            target >= self.generation,
+            "Cannot fast forward from generation {} back to {}",
+            self.generation,
+            target
+        );
+
+        let cycle = self.find_cycle(algorithm, limit.min(target - self.generation));
+        let steps = match cycle {
+            Some(Cycle { start, length }) if target > start => {
+                start - self.generation + (target - start) % length
+            }
+            _ => target - self.generation,
+        };
+
+        for _ in 0..steps {
+            self.state.step();
+        }
+        self.generation = target;
+
+        cycle
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    // Counts up through `tail` states and then loops over `length` more, recording every step
+    // taken across all clones in `steps`.
+    #[derive(Clone, Debug)]
+    struct Rho {
+        value: usize,
+        tail: usize,
+        length: usize,
+        steps: Rc<Cell<usize>>,
+    }
+
+    impl Rho {
+        fn new(tail: usize, length: usize) -> Self {
+            Rho {
+                value: 0,
+                tail,
+                length,
+                steps: Rc::new(Cell::new(0)),
+            }
+        }
+
+        // Value at `generation`, computed directly.
+        fn value_at(&self, generation: usize) -> usize {
+            if generation < self.tail {
+                generation
+            } else {
+                self.tail + (generation - self.tail) % self.length
+            }
+        }
+    }
+
+    impl PartialEq for Rho {
+        fn eq(&self, other: &Self) -> bool {
+            self.value == other.value
+        }
+    }
+
+    impl Eq for Rho {}
+
+    impl Hash for Rho {
+        fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+            self.value.hash(state);
+        }
+    }
+
+    impl Automaton for Rho {
+        type Stats = usize;
+
+        fn step(&mut self) -> usize {
+            self.steps.set(self.steps.get() + 1);
+            self.value = if self.value + 1 < self.tail + self.length {
+                self.value + 1
+            } else {
+                self.tail
+            };
+            self.value
+        }
+    }
+
+    const ALGORITHMS: [CycleAlgorithm; 3] = [
+        CycleAlgorithm::Hashing,
+        CycleAlgorithm::Floyd,
+        CycleAlgorithm::Brent,
+    ];
+
+    #[test]
+    fn algorithms_agree() {
+        for (tail, length) in [(0, 1), (0, 5), (3, 1), (7, 4), (20, 13)] {
+            let rho = Rho::new(tail, length);
+            for algorithm in ALGORITHMS {
+                assert_eq!(
+                    find_cycle(&rho, algorithm, 1000),
+                    Some(Cycle {
+                        start: tail,
+                        length
+                    }),
+                    "{:?} on tail {}, length {}",
+                    algorithm,
+                    tail,
+                    length
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn limit_stops_search() {
+        for algorithm in ALGORITHMS {
+            assert_eq!(find_cycle(&Rho::new(50, 50), algorithm, 10), None);
+        }
+    }
+
+    #[test]
+    fn limit_means_the_same_for_every_algorithm() {
+        for (tail, length) in [(0, 1), (0, 5), (1, 8), (3, 1), (7, 4), (20, 13), (33, 2)] {
+            let rho = Rho::new(tail, length);
+            for algorithm in ALGORITHMS {
+                assert_eq!(
+                    find_cycle(&rho, algorithm, tail + length),
+                    Some(Cycle {
+                        start: tail,
+                        length
+                    }),
+                    "{:?} on tail {}, length {}",
+                    algorithm,
+                    tail,
+                    length
+                );
+                assert_eq!(
+                    find_cycle(&rho, algorithm, tail + length - 1),
+                    None,
+                    "{:?} on tail {}, length {}",
+                    algorithm,
+                    tail,
+                    length
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn fast_forward_matches_stepping() {
+        for algorithm in ALGORITHMS {
+            for target in [0, 5, 7, 10, 11, 1_000_000] {
+                let mut simulation = Simulation::new(Rho::new(7, 4));
+                simulation.run(2);
+                simulation.fast_forward(target.max(2), algorithm, 100);
+                assert_eq!(simulation.generation, target.max(2));
+                assert_eq!(
+                    simulation.state.value,
+                    simulation.state.value_at(target.max(2))
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn fast_forward_does_not_search_past_target() {
+        // Steps taken searching, on top of the 10 taken to reach the target.
+        for (algorithm, search) in [
+            (CycleAlgorithm::Hashing, 10),
+            (CycleAlgorithm::Floyd, 30),
+            (CycleAlgorithm::Brent, 31),
+        ] {
+            let rho = Rho::new(100_000, 3);
+            let steps = rho.steps.clone();
+            let mut simulation = Simulation::new(rho);
+            assert_eq!(simulation.fast_forward(10, algorithm, usize::MAX), None);
+            assert_eq!(simulation.state.value, 10);
+            assert_eq!(steps.get(), search + 10, "{:?}", algorithm);
+        }
+    }
+
+    #[test]
+    fn run_until_and_observer() {
+        let mut seen = vec![];
+        let mut simulation = Simulation::new(Rho::new(5, 2))
+            .with_observer(|generation, state: &Rho, _| seen.push((generation, state.value)));
+        let stats = simulation.run_until(|state| state.value == 3);
+        assert_eq!(stats, vec![1, 2, 3]);
+        assert_eq!(simulation.generation, 3);
+        assert_eq!(simulation.into_state().value, 3);
+        assert_eq!(seen, vec![(1, 1), (2, 2), (3, 3)]);
+    }
+}
@@ -0,0 +1,600 @@
+//! Arbitrary-precision integers for answers that outgrow `i64`.
+
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+use std::ops::{Add, Div, Mul, Neg, Rem, Sub};
+use std::str::FromStr;
+
+use crate::utils::checked::OverflowError;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseBigIntError {
+    pub text: String,
+}
+
+impl Display for ParseBigIntError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "Cannot parse integer from \"{}\"", self.text)
+    }
+}
+
+impl std::error::Error for ParseBigIntError {}
+
+/// Non-negative integer stored as base 2^32 limbs, least significant first, with no trailing
+/// zero limbs (so zero is the empty vector).
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct BigUint {
+    limbs: Vec<u32>,
+}
+
+impl BigUint {
+    pub fn zero() -> Self {
+        BigUint { limbs: vec![] }
+    }
+
+    pub fn one() -> Self {
+        BigUint::from(1u64)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    fn normalize(mut self) -> Self {
+        while self.limbs.last() == Some(&0) {
+            self.limbs.pop();
+        }
+        self
+    }
+
+    pub fn to_u64(&self) -> Option<u64> {
+        match self.limbs[..] {
+            [] => Some(0),
+            [lo] => Some(lo as u64),
+            [lo, hi] => Some(((hi as u64) << 32) | lo as u64),
+            _ => None,
+        }
+    }
+
+    pub fn bits(&self) -> usize {
+        match self.limbs.last() {
+            Some(top) => self.limbs.len() * 32 - top.leading_zeros() as usize,
+            None => 0,
+        }
+    }
+
+    fn bit(&self, idx: usize) -> bool {
+        self.limbs
+            .get(idx / 32)
+            .is_some_and(|limb| (limb >> (idx % 32)) & 1 == 1)
+    }
+
+    fn shl1_with(&mut self, low_bit: bool) {
+        let mut carry = low_bit as u32;
+        for limb in self.limbs.iter_mut() {
+            let next_carry = *limb >> 31;
+            *limb = (*limb << 1) | carry;
+            carry = next_carry;
+        }
+        if carry != 0 {
+            self.limbs.push(carry);
+        }
+    }
+
+    /// Divides in place by a single limb, returning the remainder.
+    fn divmod_small(&mut self, divisor: u32) -> u32 {
+        let mut rem: u64 = 0;
+        for limb in self.limbs.iter_mut().rev() {
+            let cur = (rem << 32) | *limb as u64;
+            *limb = (cur / divisor as u64) as u32;
+            rem = cur % divisor as u64;
+        }
+
+        *self = std::mem::take(self).normalize();
+        rem as u32
+    }
+
+    /// Quotient and remainder. Panics if `divisor` is zero.
+    pub fn divmod(&self, divisor: &BigUint) -> (BigUint, BigUint) {
+        assert!(!divisor.is_zero(), "Division by zero");
+        if let [small] = divisor.limbs[..] {
+            let mut quotient = self.clone();
+            let rem = quotient.divmod_small(small);
+            return (quotient, BigUint::from(rem as u64));
+        }
+
+        // Binary long division, one bit of the dividend at a time.
+        let mut quotient = BigUint {
+            limbs: vec![0; self.limbs.len()],
+        };
+        let mut rem = BigUint::zero();
+        for idx in (0..self.bits()).rev() {
+            rem.shl1_with(self.bit(idx));
+            if rem >= *divisor {
+                rem = &rem - divisor;
+                quotient.limbs[idx / 32] |= 1 << (idx % 32);
+            }
+        }
+
+        (quotient.normalize(), rem)
+    }
+
+    pub fn checked_sub(&self, rhs: &BigUint) -> Option<BigUint> {
+        if *self < *rhs {
+            return None;
+        }
+
+        let mut limbs = Vec::with_capacity(self.limbs.len());
+        let mut borrow: i64 = 0;
+        for (idx, &limb) in self.limbs.iter().enumerate() {
+            let mut cur = limb as i64 - *rhs.limbs.get(idx).unwrap_or(&0) as i64 - borrow;
+            borrow = 0;
+            if cur < 0 {
+                cur += 1 << 32;
+                borrow = 1;
+            }
+            limbs.push(cur as u32);
+        }
+
+        Some(BigUint { limbs }.normalize())
+    }
+
+    pub fn pow(&self, mut exp: u64) -> BigUint {
+        let mut res = BigUint::one();
+        let mut base = self.clone();
+        while exp > 0 {
+            if exp & 1 == 1 {
+                res = &res * &base;
+            }
+            base = &base * &base;
+            exp >>= 1;
+        }
+
+        res
+    }
+}
+
+impl From<u64> for BigUint {
+    fn from(val: u64) -> Self {
+        BigUint {
+            limbs: vec![val as u32, (val >> 32) as u32],
+        }
+        .normalize()
+    }
+}
+
+impl TryFrom<&BigUint> for i64 {
+    type Error = OverflowError;
+
+    fn try_from(val: &BigUint) -> Result<i64, OverflowError> {
+        val.to_u64()
+            .and_then(|val| i64::try_from(val).ok())
+            .ok_or_else(|| OverflowError::new(format!("{} does not fit in i64", val)))
+    }
+}
+
+impl FromStr for BigUint {
+    type Err = ParseBigIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let digits = s.strip_prefix('+').unwrap_or(s);
+        if digits.is_empty() || !digits.bytes().all(|c| c.is_ascii_digit()) {
+            return Err(ParseBigIntError {
+                text: s.to_string(),
+            });
+        }
+
+        // Consume 9 decimal digits at a time so each chunk fits in a single limb.
+        let mut res = BigUint::zero();
+        for chunk in digits.as_bytes().chunks(9) {
+            let chunk = std::str::from_utf8(chunk).unwrap();
+            res = &(&res * &BigUint::from(10u64.pow(chunk.len() as u32)))
+                + &BigUint::from(chunk.parse::<u64>().unwrap());
+        }
+
+        Ok(res)
+    }
+}
+
+impl Ord for BigUint {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.limbs
+            .len()
+            .cmp(&other.limbs.len())
+            .then_with(|| self.limbs.iter().rev().cmp(other.limbs.iter().rev()))
+    }
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Add<&BigUint> for &BigUint {
+    type Output = BigUint;
+
+    fn add(self, rhs: &BigUint) -> BigUint {
+        let mut limbs = Vec::with_capacity(self.limbs.len().max(rhs.limbs.len()) + 1);
+        let mut carry: u64 = 0;
+        for idx in 0..self.limbs.len().max(rhs.limbs.len()) {
+            let sum = carry
+                + *self.limbs.get(idx).unwrap_or(&0) as u64
+                + *rhs.limbs.get(idx).unwrap_or(&0) as u64;
+            limbs.push(sum as u32);
+            carry = sum >> 32;
+        }
+        limbs.push(carry as u32);
+
+        BigUint { limbs }.normalize()
+    }
+}
+
+/// Panics if `rhs` is larger; see `checked_sub`.
+impl Sub<&BigUint> for &BigUint {
+    type Output = BigUint;
+
+    fn sub(self, rhs: &BigUint) -> BigUint {
+        self.checked_sub(rhs)
+            .unwrap_or_else(|| panic!("{} - {} is negative", self, rhs))
+    }
+}
+
+impl Mul<&BigUint> for &BigUint {
+    type Output = BigUint;
+
+    fn mul(self, rhs: &BigUint) -> BigUint {
+        if self.is_zero() || rhs.is_zero() {
+            return BigUint::zero();
+        }
+
+        // Schoolbook multiplication; inputs here are never more than a few hundred limbs.
+        let mut limbs = vec![0u32; self.limbs.len() + rhs.limbs.len()];
+        for (i, &lhs_limb) in self.limbs.iter().enumerate() {
+            let mut carry: u64 = 0;
+            for (j, &rhs_limb) in rhs.limbs.iter().enumerate() {
+                let cur = limbs[i + j] as u64 + lhs_limb as u64 * rhs_limb as u64 + carry;
+                limbs[i + j] = cur as u32;
+                carry = cur >> 32;
+            }
+            limbs[i + rhs.limbs.len()] = carry as u32;
+        }
+
+        BigUint { limbs }.normalize()
+    }
+}
+
+impl Div<&BigUint> for &BigUint {
+    type Output = BigUint;
+
+    fn div(self, rhs: &BigUint) -> BigUint {
+        self.divmod(rhs).0
+    }
+}
+
+impl Rem<&BigUint> for &BigUint {
+    type Output = BigUint;
+
+    fn rem(self, rhs: &BigUint) -> BigUint {
+        self.divmod(rhs).1
+    }
+}
+
+impl Display for BigUint {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+
+        // Peel off 9 decimal digits at a time, least significant first.
+        let mut chunks = vec![];
+        let mut cur = self.clone();
+        while !cur.is_zero() {
+            chunks.push(cur.divmod_small(1_000_000_000));
+        }
+
+        write!(f, "{}", chunks.pop().unwrap())?;
+        for chunk in chunks.iter().rev() {
+            write!(f, "{:09}", chunk)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Signed integer as a sign and magnitude. Zero is never negative.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct BigInt {
+    negative: bool,
+    magnitude: BigUint,
+}
+
+impl BigInt {
+    pub fn zero() -> Self {
+        BigInt::default()
+    }
+
+    pub fn one() -> Self {
+        BigInt::from(1i64)
+    }
+
+    fn new(negative: bool, magnitude: BigUint) -> Self {
+        BigInt {
+            negative: negative && !magnitude.is_zero(),
+            magnitude,
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.magnitude.is_zero()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    pub fn abs(&self) -> BigUint {
+        self.magnitude.clone()
+    }
+
+    /// Quotient rounded towards zero and remainder with the sign of `self`, matching `i64`'s
+    /// `/` and `%`. Panics if `divisor` is zero.
+    pub fn divmod(&self, divisor: &BigInt) -> (BigInt, BigInt) {
+        let (quotient, rem) = self.magnitude.divmod(&divisor.magnitude);
+        (
+            BigInt::new(self.negative != divisor.negative, quotient),
+            BigInt::new(self.negative, rem),
+        )
+    }
+
+    pub fn pow(&self, exp: u64) -> BigInt {
+        BigInt::new(self.negative && exp % 2 == 1, self.magnitude.pow(exp))
+    }
+}
+
+impl From<i64> for BigInt {
+    fn from(val: i64) -> Self {
+        BigInt::new(val < 0, BigUint::from(val.unsigned_abs()))
+    }
+}
+
+impl From<BigUint> for BigInt {
+    fn from(magnitude: BigUint) -> Self {
+        BigInt::new(false, magnitude)
+    }
+}
+
+impl TryFrom<&BigInt> for i64 {
+    type Error = OverflowError;
+
+    fn try_from(val: &BigInt) -> Result<i64, OverflowError> {
+        let err = || OverflowError::new(format!("{} does not fit in i64", val));
+        let magnitude = val.magnitude.to_u64().ok_or_else(err)?;
+        if val.negative {
+            0i64.checked_sub_unsigned(magnitude).ok_or_else(err)
+        } else {
+            i64::try_from(magnitude).map_err(|_| err())
+        }
+    }
+}
+
+impl FromStr for BigInt {
+    type Err = ParseBigIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseBigIntError {
+            text: s.to_string(),
+        };
+        match s.strip_prefix('-') {
+            Some(rest) if !rest.starts_with('+') => {
+                Ok(BigInt::new(true, rest.parse().map_err(|_| err())?))
+            }
+            Some(_) => Err(err()),
+            None => Ok(BigInt::new(false, s.parse().map_err(|_| err())?)),
+        }
+    }
+}
+
+impl Ord for BigInt {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, false) => self.magnitude.cmp(&other.magnitude),
+            (true, true) => other.magnitude.cmp(&self.magnitude),
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+        }
+    }
+}
+
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Neg for &BigInt {
+    type Output = BigInt;
+
+    fn neg(self) -> BigInt {
+        BigInt::new(!self.negative, self.magnitude.clone())
+    }
+}
+
+impl Neg for BigInt {
+    type Output = BigInt;
+
+    fn neg(self) -> BigInt {
+        -&self
+    }
+}
+
+impl Add<&BigInt> for &BigInt {
+    type Output = BigInt;
+
+    fn add(self, rhs: &BigInt) -> BigInt {
+        if self.negative == rhs.negative {
+            return BigInt::new(self.negative, &self.magnitude + &rhs.magnitude);
+        }
+
+        // Opposite signs: the larger magnitude wins and keeps its sign.
+        match self.magnitude.cmp(&rhs.magnitude) {
+            Ordering::Less => BigInt::new(rhs.negative, &rhs.magnitude - &self.magnitude),
+            _ => BigInt::new(self.negative, &self.magnitude - &rhs.magnitude),
+        }
+    }
+}
+
+impl Sub<&BigInt> for &BigInt {
+    type Output = BigInt;
+
+    fn sub(self, rhs: &BigInt) -> BigInt {
+        self + &-rhs
+    }
+}
+
+impl Mul<&BigInt> for &BigInt {
+    type Output = BigInt;
+
+    fn mul(self, rhs: &BigInt) -> BigInt {
+        BigInt::new(
+            self.negative != rhs.negative,
+            &self.magnitude * &rhs.magnitude,
+        )
+    }
+}
+
+impl Div<&BigInt> for &BigInt {
+    type Output = BigInt;
+
+    fn div(self, rhs: &BigInt) -> BigInt {
+        self.divmod(rhs).0
+    }
+}
+
+impl Rem<&BigInt> for &BigInt {
+    type Output = BigInt;
+
+    fn rem(self, rhs: &BigInt) -> BigInt {
+        self.divmod(rhs).1
+    }
+}
+
+impl Display for BigInt {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        if self.negative {
+            write!(f, "-")?;
+        }
+        write!(f, "{}", self.magnitude)
+    }
+}
+
+/// Owned-operand versions of the binary operators, forwarding to the reference ones.
+macro_rules! forward_owned_ops {
+    ($t:ty: $($imp:ident::$method:ident),*) => {
+        $(
+            impl $imp for $t {
+                type Output = $t;
+
+                fn $method(self, rhs: $t) -> $t {
+                    (&self).$method(&rhs)
+                }
+            }
+        )*
+    };
+}
+
+forward_owned_ops!(BigUint: Add::add, Sub::sub, Mul::mul, Div::div, Rem::rem);
+forward_owned_ops!(BigInt: Add::add, Sub::sub, Mul::mul, Div::div, Rem::rem);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn big(s: &str) -> BigUint {
+        s.parse().unwrap()
+    }
+
+    fn int(s: &str) -> BigInt {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_and_prints() {
+        for text in ["0", "7", "4294967296", "123456789012345678901234567890"] {
+            assert_eq!(big(text).to_string(), text);
+        }
+        assert_eq!(big("+0042").to_string(), "42");
+        assert!("".parse::<BigUint>().is_err());
+        assert!("-1".parse::<BigUint>().is_err());
+        assert!("1x".parse::<BigUint>().is_err());
+
+        assert_eq!(
+            int("-98765432109876543210").to_string(),
+            "-98765432109876543210"
+        );
+        assert_eq!(int("-0").to_string(), "0");
+        assert!(!int("-0").is_negative());
+    }
+
+    #[test]
+    fn unsigned_arithmetic_matches_u128() {
+        let pairs: [(u128, u128); 4] = [
+            (0, 1),
+            (u64::MAX as u128, u64::MAX as u128),
+            (1 << 100, 12345),
+            (98765432109876543210, 4294967297),
+        ];
+        for (a, b) in pairs {
+            let (x, y) = (big(&a.to_string()), big(&b.to_string()));
+            assert_eq!((&x + &y).to_string(), (a + b).to_string());
+            assert_eq!(
+                (&x * &y).to_string(),
+                a.wrapping_mul(b).to_string(),
+                "{a} * {b}"
+            );
+            assert_eq!((&x / &y).to_string(), (a / b).to_string());
+            assert_eq!((&x % &y).to_string(), (a % b).to_string());
+            assert_eq!(
+                x.checked_sub(&y).map(|v| v.to_string()),
+                a.checked_sub(b).map(|v| v.to_string())
+            );
+            assert_eq!(x.cmp(&y), a.cmp(&b));
+            assert_eq!(x.bits(), 128 - a.leading_zeros() as usize);
+        }
+    }
+
+    #[test]
+    fn powers_and_conversions() {
+        let two = BigUint::from(2);
+        assert_eq!(two.pow(100), big("1267650600228229401496703205376"));
+        assert_eq!(BigUint::zero().pow(0), BigUint::one());
+        assert_eq!(two.pow(63).to_u64(), Some(1 << 63));
+        assert_eq!(two.pow(64).to_u64(), None);
+        assert_eq!(i64::try_from(&two.pow(62)), Ok(1 << 62));
+        assert!(i64::try_from(&two.pow(63)).is_err());
+
+        assert_eq!(BigInt::from(-3).pow(3), BigInt::from(-27));
+        assert_eq!(BigInt::from(-3).pow(4), BigInt::from(81));
+        assert_eq!(i64::try_from(&BigInt::from(i64::MIN)), Ok(i64::MIN));
+        assert!(i64::try_from(&(BigInt::from(i64::MIN) - BigInt::one())).is_err());
+    }
+
+    #[test]
+    fn signed_arithmetic_matches_i64() {
+        let values = [-17, -5, -1, 0, 1, 3, 17, 1 << 40];
+        for a in values {
+            for b in values {
+                let (x, y) = (BigInt::from(a), BigInt::from(b));
+                assert_eq!(&x + &y, BigInt::from(a + b));
+                assert_eq!(&x - &y, BigInt::from(a - b));
+                assert_eq!((&x * &y).to_string(), (a as i128 * b as i128).to_string());
+                assert_eq!(x.cmp(&y), a.cmp(&b));
+                if b != 0 {
+                    assert_eq!(x.divmod(&y), (BigInt::from(a / b), BigInt::from(a % b)));
+                }
+            }
+            assert_eq!(-BigInt::from(a), BigInt::from(-a));
+            assert_eq!(BigInt::from(a).abs(), BigUint::from(a.unsigned_abs()));
+        }
+    }
+}
@@ -0,0 +1,110 @@
+//! Overflow-checked integer arithmetic that reports what overflowed instead of wrapping.
+
+use std::fmt::{Display, Formatter};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OverflowError {
+    pub description: String,
+}
+
+impl OverflowError {
+    pub fn new(description: impl Into<String>) -> Self {
+        OverflowError {
+            description: description.into(),
+        }
+    }
+}
+
+impl Display for OverflowError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "Arithmetic overflow: {}", self.description)
+    }
+}
+
+impl std::error::Error for OverflowError {}
+
+pub trait CheckedArith: Sized + Copy + Display {
+    fn try_add(self, rhs: Self) -> Result<Self, OverflowError>;
+    #[allow(dead_code)]
+    fn try_sub(self, rhs: Self) -> Result<Self, OverflowError>;
+    fn try_mul(self, rhs: Self) -> Result<Self, OverflowError>;
+    fn try_pow(self, exp: u32) -> Result<Self, OverflowError>;
+}
+
+macro_rules! impl_checked_arith {
+    ($($t:ty),*) => {
+        $(
+            impl CheckedArith for $t {
+                fn try_add(self, rhs: Self) -> Result<Self, OverflowError> {
+                    self.checked_add(rhs).ok_or_else(|| overflow(self, "+", rhs))
+                }
+
+                fn try_sub(self, rhs: Self) -> Result<Self, OverflowError> {
+                    self.checked_sub(rhs).ok_or_else(|| overflow(self, "-", rhs))
+                }
+
+                fn try_mul(self, rhs: Self) -> Result<Self, OverflowError> {
+                    self.checked_mul(rhs).ok_or_else(|| overflow(self, "*", rhs))
+                }
+
+                fn try_pow(self, exp: u32) -> Result<Self, OverflowError> {
+                    self.checked_pow(exp).ok_or_else(|| overflow(self, "^", exp))
+                }
+            }
+        )*
+    };
+}
+
+impl_checked_arith!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+fn overflow<T: Display>(lhs: T, op: &str, rhs: impl Display) -> OverflowError {
+    OverflowError::new(format!(
+        "{} {} {} does not fit in {}",
+        lhs,
+        op,
+        rhs,
+        std::any::type_name::<T>()
+    ))
+}
+
+/// Sums `values`, failing on the first overflow.
+pub fn checked_sum<T: CheckedArith + Default>(
+    values: impl IntoIterator<Item = T>,
+) -> Result<T, OverflowError> {
+    values
+        .into_iter()
+        .try_fold(T::default(), |sum, val| sum.try_add(val))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_what_overflowed() {
+        assert_eq!(2i64.try_add(3), Ok(5));
+        assert_eq!(
+            i64::MAX.try_add(1).unwrap_err().to_string(),
+            "Arithmetic overflow: 9223372036854775807 + 1 does not fit in i64"
+        );
+        assert_eq!(
+            0u8.try_sub(1).unwrap_err().description,
+            "0 - 1 does not fit in u8"
+        );
+        assert_eq!(16i32.try_mul(-2), Ok(-32));
+        assert!(i32::MIN.try_mul(-1).is_err());
+        assert_eq!(10u64.try_pow(19), Ok(10_000_000_000_000_000_000));
+        assert_eq!(
+            10u64.try_pow(20).unwrap_err().description,
+            "10 ^ 20 does not fit in u64"
+        );
+    }
+
+    #[test]
+    fn sums_until_overflow() {
+        assert_eq!(checked_sum(Vec::<i64>::new()), Ok(0));
+        assert_eq!(checked_sum([1u8, 2, 3]), Ok(6));
+        assert!(checked_sum([200u8, 50, 6]).is_err());
+        assert_eq!(checked_sum([i64::MAX, -1, 1]), Ok(i64::MAX));
+    }
+}
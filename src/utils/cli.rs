@@ -0,0 +1,50 @@
+//! Minimal `--name value` option parsing for per-day command lines.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+#[derive(Debug)]
+pub struct Args {
+    values: HashMap<String, String>,
+}
+
+impl Args {
+    /// Parses `--name value` pairs, rejecting any name not listed in `allowed`.
+    pub fn parse(args: &[String], allowed: &[&str]) -> Result<Self, String> {
+        let mut values: HashMap<String, String> = HashMap::new();
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            let name = arg
+                .strip_prefix("--")
+                .ok_or_else(|| format!("Expected an option, found \"{}\"", arg))?;
+            if !allowed.contains(&name) {
+                return Err(format!(
+                    "Unknown option --{}, expected one of: --{}",
+                    name,
+                    allowed.join(", --")
+                ));
+            }
+
+            let value = iter
+                .next()
+                .ok_or_else(|| format!("Option --{} is missing a value", name))?;
+            values.insert(name.to_string(), value.clone());
+        }
+
+        Ok(Args { values })
+    }
+
+    pub fn get<T: FromStr>(&self, name: &str) -> Result<Option<T>, String> {
+        match self.values.get(name) {
+            Some(value) => value
+                .parse::<T>()
+                .map(Some)
+                .map_err(|_| format!("Invalid value \"{}\" for --{}", value, name)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_or<T: FromStr>(&self, name: &str, default: T) -> Result<T, String> {
+        Ok(self.get(name)?.unwrap_or(default))
+    }
+}
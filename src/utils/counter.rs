@@ -0,0 +1,228 @@
+//! Multiset that counts how many times each key has been seen.
+
+use std::collections::BTreeMap;
+use std::ops::{Add, AddAssign, Sub, SubAssign};
+
+use crate::utils::checked::{checked_sum, CheckedArith, OverflowError};
+
+/// Counts are always positive; keys whose count drops to zero are removed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Counter<K: Ord> {
+    counts: BTreeMap<K, i64>,
+}
+
+impl<K: Ord> Counter<K> {
+    pub fn new() -> Self {
+        Counter {
+            counts: BTreeMap::new(),
+        }
+    }
+
+    /// Builds a counter from (key, count) pairs, summing repeated keys.
+    pub fn from_counts(counts: impl IntoIterator<Item = (K, i64)>) -> Self {
+        let mut res = Counter::new();
+        for (key, count) in counts {
+            res.increment(key, count);
+        }
+
+        res
+    }
+
+    pub fn insert(&mut self, key: K) {
+        self.increment(key, 1);
+    }
+
+    /// Panics if the count no longer fits in an i64; see [`Counter::try_increment`].
+    pub fn increment(&mut self, key: K, count: i64) {
+        self.try_increment(key, count).unwrap();
+    }
+
+    /// Adds `count` occurrences of `key`, or removes them if `count` is negative. On overflow
+    /// the counter is left unchanged.
+    pub fn try_increment(&mut self, key: K, count: i64) -> Result<(), OverflowError> {
+        if count < 0 {
+            self.decrement(key, count.try_mul(-1)?);
+        } else if count > 0 {
+            let cur = self.counts.entry(key).or_insert(0);
+            *cur = cur.try_add(count)?;
+        }
+
+        Ok(())
+    }
+
+    /// Removes up to `count` occurrences of `key`.
+    pub fn decrement(&mut self, key: K, count: i64) {
+        if let Some(cur) = self.counts.get_mut(&key) {
+            *cur -= count;
+            if *cur <= 0 {
+                self.counts.remove(&key);
+            }
+        }
+    }
+
+    /// Removes every occurrence of `key`, returning how many there were.
+    #[allow(dead_code)]
+    pub fn remove(&mut self, key: &K) -> i64 {
+        self.counts.remove(key).unwrap_or(0)
+    }
+
+    pub fn get(&self, key: &K) -> i64 {
+        *self.counts.get(key).unwrap_or(&0)
+    }
+
+    /// Number of distinct keys.
+    #[allow(dead_code)]
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// Panics if the sum doesn't fit in an i64; see [`Counter::try_total`].
+    #[allow(dead_code)]
+    pub fn total(&self) -> i64 {
+        self.try_total().unwrap()
+    }
+
+    /// Sum of all counts.
+    pub fn try_total(&self) -> Result<i64, OverflowError> {
+        checked_sum(self.counts.values().copied())
+    }
+
+    /// (key, count) pairs in key order.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (&K, i64)> {
+        self.counts.iter().map(|(key, &count)| (key, count))
+    }
+
+    pub fn keys(&self) -> impl DoubleEndedIterator<Item = &K> {
+        self.counts.keys()
+    }
+
+    /// The `n` keys with the highest counts, highest first. Ties are broken by key order.
+    #[allow(dead_code)]
+    pub fn most_common(&self, n: usize) -> Vec<(&K, i64)> {
+        let mut res = self.iter().collect::<Vec<(&K, i64)>>();
+        res.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+        res.truncate(n);
+        res
+    }
+
+    /// The `n` keys with the lowest counts, lowest first. Ties are broken by key order.
+    #[allow(dead_code)]
+    pub fn least_common(&self, n: usize) -> Vec<(&K, i64)> {
+        let mut res = self.iter().collect::<Vec<(&K, i64)>>();
+        res.sort_by_key(|(_, count)| *count);
+        res.truncate(n);
+        res
+    }
+}
+
+impl<K: Ord> FromIterator<K> for Counter<K> {
+    fn from_iter<I: IntoIterator<Item = K>>(iter: I) -> Self {
+        let mut res = Counter::new();
+        res.extend(iter);
+        res
+    }
+}
+
+impl<K: Ord> Extend<K> for Counter<K> {
+    fn extend<I: IntoIterator<Item = K>>(&mut self, iter: I) {
+        for key in iter {
+            self.insert(key);
+        }
+    }
+}
+
+impl<K: Ord> AddAssign for Counter<K> {
+    fn add_assign(&mut self, rhs: Self) {
+        for (key, count) in rhs.counts {
+            self.increment(key, count);
+        }
+    }
+}
+
+impl<K: Ord> Add for Counter<K> {
+    type Output = Self;
+
+    fn add(mut self, rhs: Self) -> Self {
+        self += rhs;
+        self
+    }
+}
+
+/// Multiset difference: counts are reduced but never go below zero.
+impl<K: Ord> SubAssign for Counter<K> {
+    fn sub_assign(&mut self, rhs: Self) {
+        for (key, count) in rhs.counts {
+            self.decrement(key, count);
+        }
+    }
+}
+
+impl<K: Ord> Sub for Counter<K> {
+    type Output = Self;
+
+    fn sub(mut self, rhs: Self) -> Self {
+        self -= rhs;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_keys() {
+        let counter: Counter<char> = "abracadabra".chars().collect();
+        assert_eq!(counter.get(&'a'), 5);
+        assert_eq!(counter.get(&'z'), 0);
+        assert_eq!(counter.len(), 5);
+        assert_eq!(counter.total(), 11);
+        assert_eq!(
+            counter.iter().collect::<Vec<_>>(),
+            vec![(&'a', 5), (&'b', 2), (&'c', 1), (&'d', 1), (&'r', 2)]
+        );
+    }
+
+    #[test]
+    fn most_and_least_common_break_ties_by_key() {
+        let counter: Counter<char> = "abracadabra".chars().collect();
+        assert_eq!(
+            counter.most_common(3),
+            vec![(&'a', 5), (&'b', 2), (&'r', 2)]
+        );
+        assert_eq!(counter.least_common(2), vec![(&'c', 1), (&'d', 1)]);
+        assert_eq!(counter.most_common(10).len(), 5);
+    }
+
+    #[test]
+    fn arithmetic_never_goes_negative() {
+        let lhs = Counter::from_counts([(1, 3), (2, 1)]);
+        let rhs = Counter::from_counts([(2, 5), (3, 2)]);
+        assert_eq!(
+            lhs.clone() + rhs.clone(),
+            Counter::from_counts([(1, 3), (2, 6), (3, 2)])
+        );
+        assert_eq!(lhs - rhs, Counter::from_counts([(1, 3)]));
+
+        let mut counter = Counter::from_counts([(1, 3)]);
+        counter.increment(1, -2);
+        assert_eq!(counter.get(&1), 1);
+        assert_eq!(counter.remove(&1), 1);
+        assert!(counter.is_empty());
+    }
+
+    #[test]
+    fn overflow_is_reported() {
+        let mut counter = Counter::from_counts([(1, i64::MAX), (2, 1)]);
+        assert!(counter.try_total().is_err());
+        assert!(counter.try_increment(1, 1).is_err());
+        assert_eq!(counter.get(&1), i64::MAX);
+        assert!(counter.try_increment(2, i64::MIN).is_err());
+        assert_eq!(counter.get(&2), 1);
+    }
+}
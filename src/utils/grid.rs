@@ -0,0 +1,349 @@
+//! Dense, row-major 2D grid shared between the grid-based solutions.
+
+use std::fmt::{Display, Formatter};
+
+use crate::utils::search::Graph;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Grid<T = i64> {
+    pub data: Vec<Vec<T>>,
+}
+
+impl Grid {
+    /// Parses a grid of single digits, one row per line.
+    pub fn from_data(data: Vec<String>) -> Self {
+        Grid {
+            data: data
+                .into_iter()
+                .map(|s| {
+                    s.chars()
+                        .map(|c| str::parse::<i64>(&c.to_string()))
+                        .map(Result::unwrap)
+                        .collect::<Vec<i64>>()
+                })
+                .collect::<Vec<Vec<i64>>>(),
+        }
+    }
+}
+
+impl<T> Grid<T> {
+    #[allow(dead_code)]
+    pub fn new(data: Vec<Vec<T>>) -> Self {
+        Grid { data }
+    }
+
+    pub fn rows(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn cols(&self) -> usize {
+        self.data.first().map_or(0, Vec::len)
+    }
+
+    fn contains(&self, row: i64, col: i64) -> bool {
+        row >= 0 && col >= 0 && (row as usize) < self.rows() && (col as usize) < self.cols()
+    }
+
+    pub fn get(&self, row: i64, col: i64) -> Option<&T> {
+        if self.contains(row, col) {
+            Some(&self.data[row as usize][col as usize])
+        } else {
+            None
+        }
+    }
+
+    pub fn at_mut(&mut self, row: i64, col: i64) -> Option<&mut T> {
+        if self.contains(row, col) {
+            Some(&mut self.data[row as usize][col as usize])
+        } else {
+            None
+        }
+    }
+
+    /// All (row, col) positions in row-major order.
+    pub fn positions(&self) -> impl Iterator<Item = (i64, i64)> {
+        let rows = self.rows() as i64;
+        let cols = self.cols() as i64;
+        (0..rows).flat_map(move |row| (0..cols).map(move |col| (row, col)))
+    }
+
+    /// Mirrors the grid left to right.
+    pub fn flip_horizontal(&mut self) {
+        for row in self.data.iter_mut() {
+            row.reverse();
+        }
+    }
+
+    /// Mirrors the grid top to bottom.
+    pub fn flip_vertical(&mut self) {
+        self.data.reverse();
+    }
+
+    pub fn rotate_180(&mut self) {
+        self.flip_horizontal();
+        self.flip_vertical();
+    }
+}
+
+impl<T: Copy> Grid<T> {
+    pub fn at(&self, row: i64, col: i64) -> Option<T> {
+        self.get(row, col).copied()
+    }
+}
+
+impl<T: Clone> Grid<T> {
+    pub fn transposed(&self) -> Self {
+        Grid {
+            data: (0..self.cols())
+                .map(|col| self.data.iter().map(|row| row[col].clone()).collect())
+                .collect(),
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn transpose(&mut self) {
+        *self = self.transposed();
+    }
+
+    /// Rotated a quarter turn clockwise.
+    pub fn rotated_90(&self) -> Self {
+        let mut res = self.transposed();
+        res.flip_horizontal();
+        res
+    }
+
+    #[allow(dead_code)]
+    pub fn rotate_90(&mut self) {
+        *self = self.rotated_90();
+    }
+
+    #[allow(dead_code)]
+    pub fn rotated_180(&self) -> Self {
+        let mut res = self.clone();
+        res.rotate_180();
+        res
+    }
+
+    /// Rotated a quarter turn anticlockwise.
+    pub fn rotated_270(&self) -> Self {
+        let mut res = self.transposed();
+        res.flip_vertical();
+        res
+    }
+
+    #[allow(dead_code)]
+    pub fn rotate_270(&mut self) {
+        *self = self.rotated_270();
+    }
+
+    #[allow(dead_code)]
+    pub fn flipped_horizontal(&self) -> Self {
+        let mut res = self.clone();
+        res.flip_horizontal();
+        res
+    }
+
+    #[allow(dead_code)]
+    pub fn flipped_vertical(&self) -> Self {
+        let mut res = self.clone();
+        res.flip_vertical();
+        res
+    }
+
+    /// The `height` x `width` sub-grid whose top left corner is at (row, col), or `None` if it
+    /// does not fit inside the grid.
+    pub fn cropped(&self, row: usize, col: usize, height: usize, width: usize) -> Option<Self> {
+        let fits = |start: usize, len: usize, limit: usize| {
+            start.checked_add(len).is_some_and(|end| end <= limit)
+        };
+        if !fits(row, height, self.rows()) || !fits(col, width, self.cols()) {
+            return None;
+        }
+
+        Some(Grid {
+            data: self.data[row..row + height]
+                .iter()
+                .map(|r| r[col..col + width].to_vec())
+                .collect(),
+        })
+    }
+
+    #[allow(dead_code)]
+    pub fn crop(&mut self, row: usize, col: usize, height: usize, width: usize) -> bool {
+        match self.cropped(row, col, height, width) {
+            Some(res) => {
+                *self = res;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Repeats the grid `tile_rows` times downwards and `tile_cols` times across. Each cell is
+    /// passed through `mapper(value, tile_row, tile_col)` for the tile it ends up in.
+    #[allow(dead_code)]
+    pub fn tiled(
+        &self,
+        tile_rows: usize,
+        tile_cols: usize,
+        mapper: impl Fn(&T, usize, usize) -> T,
+    ) -> Self {
+        let mut data = Vec::with_capacity(self.rows() * tile_rows);
+        for tile_row in 0..tile_rows {
+            for row in &self.data {
+                data.push(
+                    (0..tile_cols)
+                        .flat_map(|tile_col| row.iter().map(move |val| (val, tile_col)))
+                        .map(|(val, tile_col)| mapper(val, tile_row, tile_col))
+                        .collect(),
+                );
+            }
+        }
+
+        Grid { data }
+    }
+
+    /// Surrounds the grid with `border` cells of `fill` on every side.
+    #[allow(dead_code)]
+    pub fn expanded(&self, border: usize, fill: T) -> Self {
+        let width = self.cols() + 2 * border;
+        let mut data = vec![vec![fill.clone(); width]; border];
+        for row in &self.data {
+            let mut new_row = Vec::with_capacity(width);
+            new_row.extend(std::iter::repeat_n(fill.clone(), border));
+            new_row.extend(row.iter().cloned());
+            new_row.extend(std::iter::repeat_n(fill.clone(), border));
+            data.push(new_row);
+        }
+        data.extend(std::iter::repeat_n(vec![fill; width], border));
+
+        Grid { data }
+    }
+}
+
+impl<T> Graph for Grid<T> {
+    type Node = (i64, i64);
+
+    fn neighbours(&self, (row, col): (i64, i64)) -> Vec<(i64, i64)> {
+        [
+            (row - 1, col),
+            (row + 1, col),
+            (row, col - 1),
+            (row, col + 1),
+        ]
+        .into_iter()
+        .filter(|&(r, c)| self.contains(r, c))
+        .collect()
+    }
+}
+
+impl<T: Display> Display for Grid<T> {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{}",
+            self.data
+                .iter()
+                .map(|row| row
+                    .iter()
+                    .map(T::to_string)
+                    .collect::<Vec<String>>()
+                    .join(" "))
+                .collect::<Vec<String>>()
+                .join("\n")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid() -> Grid {
+        Grid::new(vec![vec![1, 2, 3], vec![4, 5, 6]])
+    }
+
+    #[test]
+    fn parses_digits() {
+        let grid = Grid::from_data(vec!["123".to_string(), "456".to_string()]);
+        assert_eq!(grid, self::grid());
+        assert_eq!((grid.rows(), grid.cols()), (2, 3));
+        assert_eq!(grid.at(1, 2), Some(6));
+        assert_eq!(grid.at(2, 0), None);
+        assert_eq!(grid.at(0, -1), None);
+        assert_eq!(grid.to_string(), "1 2 3\n4 5 6\n");
+    }
+
+    #[test]
+    fn rotations_and_flips() {
+        let grid = grid();
+        assert_eq!(
+            grid.transposed().data,
+            vec![vec![1, 4], vec![2, 5], vec![3, 6]]
+        );
+        assert_eq!(
+            grid.rotated_90().data,
+            vec![vec![4, 1], vec![5, 2], vec![6, 3]]
+        );
+        assert_eq!(grid.rotated_180().data, vec![vec![6, 5, 4], vec![3, 2, 1]]);
+        assert_eq!(
+            grid.rotated_270().data,
+            vec![vec![3, 6], vec![2, 5], vec![1, 4]]
+        );
+        assert_eq!(
+            grid.flipped_horizontal().data,
+            vec![vec![3, 2, 1], vec![6, 5, 4]]
+        );
+        assert_eq!(
+            grid.flipped_vertical().data,
+            vec![vec![4, 5, 6], vec![1, 2, 3]]
+        );
+
+        let mut turned = grid.clone();
+        for _ in 0..4 {
+            turned.rotate_90();
+        }
+        assert_eq!(turned, grid);
+        turned.rotate_270();
+        turned.transpose();
+        assert_eq!(turned, grid.flipped_horizontal());
+    }
+
+    #[test]
+    fn crops_and_expansions() {
+        let mut grid = grid();
+        assert_eq!(
+            grid.cropped(0, 1, 2, 2).unwrap().data,
+            vec![vec![2, 3], vec![5, 6]]
+        );
+        assert_eq!(grid.cropped(1, 1, 2, 2), None);
+        assert_eq!(grid.cropped(1, 0, usize::MAX, 1), None);
+        assert_eq!(grid.cropped(0, usize::MAX, 1, 1), None);
+
+        let expanded = grid.expanded(1, 0);
+        assert_eq!((expanded.rows(), expanded.cols()), (4, 5));
+        assert_eq!(expanded.cropped(1, 1, 2, 3), Some(grid.clone()));
+
+        assert!(!grid.crop(0, 0, 3, 1));
+        assert!(grid.crop(1, 0, 1, 2));
+        assert_eq!(grid.data, vec![vec![4, 5]]);
+    }
+
+    #[test]
+    fn tiles_map_each_copy() {
+        let grid = Grid::new(vec![vec![1, 9]]);
+        let tiled = grid.tiled(2, 2, |&val, row, col| val + (row + col) as i64);
+        assert_eq!(tiled.data, vec![vec![1, 9, 2, 10], vec![2, 10, 3, 11]]);
+    }
+
+    #[test]
+    fn neighbours_stay_inside() {
+        let mut grid = grid();
+        assert_eq!(grid.neighbours((0, 0)), vec![(1, 0), (0, 1)]);
+        assert_eq!(grid.neighbours((1, 1)).len(), 3);
+        *grid.at_mut(1, 1).unwrap() = 0;
+        assert_eq!(grid.at(1, 1), Some(0));
+        assert!(grid.at_mut(5, 5).is_none());
+        assert_eq!(grid.positions().count(), 6);
+    }
+}
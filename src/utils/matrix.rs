@@ -0,0 +1,264 @@
+//! Square matrices for advancing linear recurrences many steps at once.
+
+use crate::utils::bigint::BigUint;
+
+/// Values that matrices can be built from.
+pub trait Scalar: Clone {
+    fn zero() -> Self;
+    fn one() -> Self;
+    fn add(&self, rhs: &Self) -> Self;
+    fn mul(&self, rhs: &Self) -> Self;
+}
+
+macro_rules! impl_scalar {
+    ($($t:ty),*) => {
+        $(
+            impl Scalar for $t {
+                fn zero() -> Self {
+                    0
+                }
+
+                fn one() -> Self {
+                    1
+                }
+
+                fn add(&self, rhs: &Self) -> Self {
+                    self + rhs
+                }
+
+                fn mul(&self, rhs: &Self) -> Self {
+                    self * rhs
+                }
+            }
+        )*
+    };
+}
+
+impl_scalar!(i64, u64, i128, u128);
+
+impl Scalar for BigUint {
+    fn zero() -> Self {
+        BigUint::zero()
+    }
+
+    fn one() -> Self {
+        BigUint::one()
+    }
+
+    fn add(&self, rhs: &Self) -> Self {
+        self + rhs
+    }
+
+    fn mul(&self, rhs: &Self) -> Self {
+        self * rhs
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Matrix<T> {
+    size: usize,
+    // Row-major.
+    cells: Vec<T>,
+}
+
+impl<T: Scalar> Matrix<T> {
+    pub fn zero(size: usize) -> Self {
+        Matrix {
+            size,
+            cells: vec![T::zero(); size * size],
+        }
+    }
+
+    pub fn identity(size: usize) -> Self {
+        let mut res = Matrix::zero(size);
+        for idx in 0..size {
+            res.set(idx, idx, T::one());
+        }
+
+        res
+    }
+
+    #[allow(dead_code)]
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> &T {
+        &self.cells[row * self.size + col]
+    }
+
+    pub fn set(&mut self, row: usize, col: usize, val: T) {
+        self.cells[row * self.size + col] = val;
+    }
+
+    #[allow(dead_code)]
+    pub fn mul(&self, rhs: &Self) -> Self {
+        self.mul_with(rhs, |val| val)
+    }
+
+    /// Multiplies, passing every cell of the result through `reduce`.
+    fn mul_with(&self, rhs: &Self, reduce: impl Fn(T) -> T) -> Self {
+        assert_eq!(self.size, rhs.size, "Cannot multiply mismatched matrices");
+        let mut res = Matrix::zero(self.size);
+        for row in 0..self.size {
+            for col in 0..self.size {
+                let mut sum = T::zero();
+                for idx in 0..self.size {
+                    sum = reduce(sum.add(&self.get(row, idx).mul(rhs.get(idx, col))));
+                }
+                res.set(row, col, sum);
+            }
+        }
+
+        res
+    }
+
+    /// Raises to `exp` by repeated squaring, i.e. O(log exp) multiplications.
+    pub fn pow(&self, exp: u64) -> Self {
+        self.pow_with(exp, |val| val)
+    }
+
+    fn pow_with(&self, mut exp: u64, reduce: impl Fn(T) -> T + Copy) -> Self {
+        let mut res = Matrix::identity(self.size);
+        let mut base = self.clone();
+        while exp > 0 {
+            if exp & 1 == 1 {
+                res = res.mul_with(&base, reduce);
+            }
+            base = base.mul_with(&base, reduce);
+            exp >>= 1;
+        }
+
+        res
+    }
+
+    /// Matrix-vector product.
+    pub fn apply(&self, vec: &[T]) -> Vec<T> {
+        self.apply_with(vec, |val| val)
+    }
+
+    fn apply_with(&self, vec: &[T], reduce: impl Fn(T) -> T) -> Vec<T> {
+        assert_eq!(self.size, vec.len(), "Vector does not match matrix size");
+        (0..self.size)
+            .map(|row| {
+                vec.iter().enumerate().fold(T::zero(), |sum, (col, val)| {
+                    reduce(sum.add(&self.get(row, col).mul(val)))
+                })
+            })
+            .collect()
+    }
+}
+
+impl Matrix<u64> {
+    /// Raises to `exp`, keeping every entry reduced modulo `modulus`.
+    #[allow(dead_code)]
+    pub fn pow_mod(&self, exp: u64, modulus: u64) -> Self {
+        narrow(self.pow_mod_wide(exp, modulus))
+    }
+
+    // Entries stay below the modulus, so every product fits in a u128 before being reduced.
+    fn pow_mod_wide(&self, exp: u64, modulus: u64) -> Matrix<u128> {
+        let wide = Matrix {
+            size: self.size,
+            cells: self
+                .cells
+                .iter()
+                .map(|&val| (val % modulus) as u128)
+                .collect(),
+        };
+
+        wide.pow_with(exp, |val| val % modulus as u128)
+    }
+}
+
+fn narrow(wide: Matrix<u128>) -> Matrix<u64> {
+    Matrix {
+        size: wide.size,
+        cells: wide.cells.into_iter().map(|val| val as u64).collect(),
+    }
+}
+
+/// State vector advanced by a fixed linear transition, i.e. `next = transition * state`.
+pub struct LinearRecurrence<T> {
+    pub transition: Matrix<T>,
+}
+
+impl<T: Scalar> LinearRecurrence<T> {
+    pub fn new(transition: Matrix<T>) -> Self {
+        LinearRecurrence { transition }
+    }
+
+    pub fn advance(&self, state: &[T], steps: u64) -> Vec<T> {
+        self.transition.pow(steps).apply(state)
+    }
+}
+
+impl LinearRecurrence<u64> {
+    pub fn advance_mod(&self, state: &[u64], steps: u64, modulus: u64) -> Vec<u64> {
+        let state = state
+            .iter()
+            .map(|&val| (val % modulus) as u128)
+            .collect::<Vec<u128>>();
+
+        self.transition
+            .pow_mod_wide(steps, modulus)
+            .apply_with(&state, |val| val % modulus as u128)
+            .into_iter()
+            .map(|val| val as u64)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fibonacci<T: Scalar>() -> Matrix<T> {
+        let mut res = Matrix::zero(2);
+        res.set(0, 0, T::one());
+        res.set(0, 1, T::one());
+        res.set(1, 0, T::one());
+        res
+    }
+
+    #[test]
+    fn multiplies_and_raises() {
+        let fib = fibonacci::<i64>();
+        assert_eq!(fib.size(), 2);
+        assert_eq!(fib.mul(&Matrix::identity(2)), fib);
+        assert_eq!(fib.pow(0), Matrix::identity(2));
+
+        let squared = fib.mul(&fib);
+        assert_eq!(fib.pow(2), squared);
+        assert_eq!(
+            (*squared.get(0, 0), *squared.get(0, 1), *squared.get(1, 1)),
+            (2, 1, 1)
+        );
+        assert_eq!(fib.pow(10).apply(&[1, 0]), vec![89, 55]);
+    }
+
+    #[test]
+    fn advances_recurrences() {
+        let recurrence = LinearRecurrence::new(fibonacci::<u64>());
+        assert_eq!(recurrence.advance(&[1, 0], 40), vec![165580141, 102334155]);
+        assert_eq!(
+            recurrence.advance_mod(&[1, 0], 40, 97),
+            vec![165580141 % 97, 102334155 % 97,]
+        );
+
+        // F(1000) mod 1e9+7, far beyond anything u64 could hold unreduced.
+        let modulus = 1_000_000_007;
+        assert_eq!(recurrence.advance_mod(&[1, 0], 999, modulus)[0], 517691607);
+        assert_eq!(
+            fibonacci::<u64>().pow_mod(999, modulus).apply(&[1, 0])[0],
+            517691607
+        );
+
+        let exact = LinearRecurrence::new(fibonacci::<BigUint>())
+            .advance(&[BigUint::one(), BigUint::zero()], 999);
+        assert_eq!(
+            (&exact[0] % &BigUint::from(modulus)).to_u64(),
+            Some(517691607)
+        );
+    }
+}
@@ -0,0 +1,398 @@
+//! Tiny parser-combinator toolkit for puzzle input formats.
+//!
+//! A parser is anything implementing [`Parser`], including plain closures over a [`Cursor`].
+//! Combinators take parsers and return new ones, e.g. a comma-separated list of integers is
+//! `separated(integer::<i64>(), literal(","))`.
+
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    // Both 1-based.
+    pub line: usize,
+    pub col: usize,
+    pub expected: String,
+    pub found: String,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: expected {}, found {}",
+            self.line, self.col, self.expected, self.found
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Position within the text being parsed. Cheap to copy, which is how parsers backtrack.
+#[derive(Clone, Copy, Debug)]
+pub struct Cursor<'a> {
+    text: &'a str,
+    offset: usize,
+    // Line number of the start of `text`, for when it is one line out of a larger input.
+    first_line: usize,
+}
+
+impl<'a> Cursor<'a> {
+    pub fn new(text: &'a str) -> Self {
+        Cursor {
+            text,
+            offset: 0,
+            first_line: 1,
+        }
+    }
+
+    fn on_line(text: &'a str, line: usize) -> Self {
+        Cursor {
+            text,
+            offset: 0,
+            first_line: line,
+        }
+    }
+
+    pub fn rest(&self) -> &'a str {
+        &self.text[self.offset..]
+    }
+
+    pub fn at_end(&self) -> bool {
+        self.offset == self.text.len()
+    }
+
+    pub fn advance(&mut self, bytes: usize) {
+        self.offset += bytes;
+    }
+
+    /// Builds an error at the current position, quoting the upcoming token as what was found.
+    pub fn error(&self, expected: impl Into<String>) -> ParseError {
+        let before = &self.text[..self.offset];
+        let line = self.first_line + before.matches('\n').count();
+        let col = before.chars().rev().take_while(|&c| c != '\n').count() + 1;
+
+        let token = self
+            .rest()
+            .split(|c: char| c.is_whitespace())
+            .next()
+            .unwrap_or("");
+        let found = if self.at_end() {
+            "end of input".to_string()
+        } else if token.is_empty() {
+            format!("{:?}", self.rest().chars().next().unwrap())
+        } else {
+            format!("\"{}\"", token)
+        };
+
+        ParseError {
+            line,
+            col,
+            expected: expected.into(),
+            found,
+        }
+    }
+}
+
+pub trait Parser<'a, T> {
+    fn parse(&self, cursor: &mut Cursor<'a>) -> Result<T, ParseError>;
+}
+
+impl<'a, T, F> Parser<'a, T> for F
+where
+    F: Fn(&mut Cursor<'a>) -> Result<T, ParseError>,
+{
+    fn parse(&self, cursor: &mut Cursor<'a>) -> Result<T, ParseError> {
+        self(cursor)
+    }
+}
+
+/// Runs `parser` over the whole of `text`, failing if anything is left over.
+pub fn parse_all<'a, T>(parser: impl Parser<'a, T>, text: &'a str) -> Result<T, ParseError> {
+    finish(&parser, Cursor::new(text))
+}
+
+/// Runs `parser` over every line, reporting errors against the line's position in `lines`.
+pub fn parse_lines<'a, T>(
+    parser: impl Parser<'a, T>,
+    lines: &'a [String],
+) -> Result<Vec<T>, ParseError> {
+    lines
+        .iter()
+        .enumerate()
+        .map(|(idx, line)| finish(&parser, Cursor::on_line(line, idx + 1)))
+        .collect()
+}
+
+fn finish<'a, T>(parser: &impl Parser<'a, T>, mut cursor: Cursor<'a>) -> Result<T, ParseError> {
+    let res = parser.parse(&mut cursor)?;
+    if !cursor.at_end() {
+        return Err(cursor.error("end of input"));
+    }
+
+    Ok(res)
+}
+
+/// Splits lines into groups separated by blank lines.
+pub fn blocks(lines: &[String]) -> Vec<&[String]> {
+    lines
+        .split(|line| line.trim().is_empty())
+        .filter(|block| !block.is_empty())
+        .collect()
+}
+
+/// Every integer appearing anywhere in `text`, ignoring whatever surrounds them. A '-' directly
+/// before the digits makes the number negative unless it follows another digit, so "x=-3..-1"
+/// gives [-3, -1] while "1-2" gives [1, 2].
+pub fn integers<T: FromStr>(text: &str) -> Result<Vec<T>, ParseError> {
+    integers_from(Cursor::new(text))
+}
+
+/// Every integer in every line, in order.
+#[allow(dead_code)]
+pub fn integers_in<T: FromStr>(lines: &[String]) -> Result<Vec<T>, ParseError> {
+    let mut res = vec![];
+    for (idx, line) in lines.iter().enumerate() {
+        res.extend(integers_from(Cursor::on_line(line, idx + 1))?);
+    }
+
+    Ok(res)
+}
+
+/// Exactly `N` integers from `text`, e.g. `let [x1, y1, x2, y2] = integers_array(line)?`.
+pub fn integers_array<T: FromStr, const N: usize>(text: &str) -> Result<[T; N], ParseError> {
+    let res = integers::<T>(text)?;
+    let found = res.len();
+    res.try_into().map_err(|_| ParseError {
+        line: 1,
+        col: 1,
+        expected: format!("{} integers", N),
+        found: format!("{} in \"{}\"", found, text),
+    })
+}
+
+fn integers_from<T: FromStr>(mut cursor: Cursor) -> Result<Vec<T>, ParseError> {
+    let number = integer::<T>();
+    let mut res = vec![];
+    let mut after_number = false;
+    while let Some(c) = cursor.rest().chars().next() {
+        let starts_number = c.is_ascii_digit()
+            || (c == '-'
+                && !after_number
+                && cursor.rest()[1..].starts_with(|n: char| n.is_ascii_digit()));
+
+        after_number = starts_number;
+        if starts_number {
+            res.push(number.parse(&mut cursor)?);
+        } else {
+            cursor.advance(c.len_utf8());
+        }
+    }
+
+    Ok(res)
+}
+
+/// Exactly `token`.
+pub fn literal<'a>(token: &'static str) -> impl Parser<'a, ()> {
+    move |cursor: &mut Cursor<'a>| {
+        if cursor.rest().starts_with(token) {
+            cursor.advance(token.len());
+            Ok(())
+        } else {
+            Err(cursor.error(format!("\"{}\"", token)))
+        }
+    }
+}
+
+/// Zero or more spaces or tabs.
+#[allow(dead_code)]
+pub fn spaces<'a>() -> impl Parser<'a, ()> {
+    move |cursor: &mut Cursor<'a>| {
+        let rest = cursor.rest();
+        cursor.advance(rest.len() - rest.trim_start_matches([' ', '\t']).len());
+        Ok(())
+    }
+}
+
+/// One or more ASCII letters.
+pub fn word<'a>() -> impl Parser<'a, &'a str> {
+    move |cursor: &mut Cursor<'a>| {
+        let rest = cursor.rest();
+        let len = rest.len()
+            - rest
+                .trim_start_matches(|c: char| c.is_ascii_alphabetic())
+                .len();
+        if len == 0 {
+            return Err(cursor.error("a word"));
+        }
+
+        cursor.advance(len);
+        Ok(&rest[..len])
+    }
+}
+
+/// An optionally signed decimal integer that fits in `T`.
+pub fn integer<'a, T: FromStr>() -> impl Parser<'a, T> {
+    move |cursor: &mut Cursor<'a>| {
+        let rest = cursor.rest();
+        let sign = usize::from(rest.starts_with(['-', '+']));
+        let digits = rest[sign..].len()
+            - rest[sign..]
+                .trim_start_matches(|c: char| c.is_ascii_digit())
+                .len();
+        if digits == 0 {
+            return Err(cursor.error("an integer"));
+        }
+
+        match rest[..sign + digits].parse::<T>() {
+            Ok(val) => {
+                cursor.advance(sign + digits);
+                Ok(val)
+            }
+            Err(_) => Err(cursor.error(format!(
+                "an integer fitting in {}",
+                std::any::type_name::<T>()
+            ))),
+        }
+    }
+}
+
+#[allow(dead_code)]
+pub fn map<'a, T, U>(parser: impl Parser<'a, T>, f: impl Fn(T) -> U) -> impl Parser<'a, U> {
+    move |cursor: &mut Cursor<'a>| parser.parse(cursor).map(&f)
+}
+
+pub fn pair<'a, A, B>(
+    first: impl Parser<'a, A>,
+    second: impl Parser<'a, B>,
+) -> impl Parser<'a, (A, B)> {
+    move |cursor: &mut Cursor<'a>| Ok((first.parse(cursor)?, second.parse(cursor)?))
+}
+
+/// `first` followed by `second`, keeping only `second`.
+#[allow(dead_code)]
+pub fn preceded<'a, A, B>(
+    first: impl Parser<'a, A>,
+    second: impl Parser<'a, B>,
+) -> impl Parser<'a, B> {
+    move |cursor: &mut Cursor<'a>| {
+        first.parse(cursor)?;
+        second.parse(cursor)
+    }
+}
+
+/// `first` followed by `second`, keeping only `first`.
+pub fn terminated<'a, A, B>(
+    first: impl Parser<'a, A>,
+    second: impl Parser<'a, B>,
+) -> impl Parser<'a, A> {
+    move |cursor: &mut Cursor<'a>| {
+        let res = first.parse(cursor)?;
+        second.parse(cursor)?;
+        Ok(res)
+    }
+}
+
+/// One or more `item`s with `separator` between each of them. Once a separator matches, an
+/// item must follow it, so a bad item is reported where it starts.
+pub fn separated<'a, T, S>(
+    item: impl Parser<'a, T>,
+    separator: impl Parser<'a, S>,
+) -> impl Parser<'a, Vec<T>> {
+    move |cursor: &mut Cursor<'a>| {
+        let mut res = vec![item.parse(cursor)?];
+        loop {
+            let mut lookahead = *cursor;
+            if separator.parse(&mut lookahead).is_err() {
+                break;
+            }
+
+            *cursor = lookahead;
+            res.push(item.parse(cursor)?);
+        }
+
+        Ok(res)
+    }
+}
+
+/// A `key` and `value` with `separator` between them, e.g. "forward 5" or "x=10".
+pub fn key_value<'a, K, S, V>(
+    key: impl Parser<'a, K>,
+    separator: impl Parser<'a, S>,
+    value: impl Parser<'a, V>,
+) -> impl Parser<'a, (K, V)> {
+    pair(terminated(key, separator), value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn separated_reports_bad_item_after_separator() {
+        let err = parse_all(separated(integer::<i64>(), literal(",")), "1,x,2").unwrap_err();
+        assert_eq!((err.line, err.col), (1, 3));
+        assert_eq!(err.expected, "an integer");
+        assert_eq!(err.found, "\"x,2\"");
+    }
+
+    #[test]
+    fn separated_stops_without_separator() {
+        let mut cursor = Cursor::new("1,2 3");
+        let res = separated(integer::<i64>(), literal(","))
+            .parse(&mut cursor)
+            .unwrap();
+        assert_eq!(res, vec![1, 2]);
+        assert_eq!(cursor.rest(), " 3");
+    }
+
+    #[test]
+    fn key_value_and_word() {
+        let parser = || key_value(word(), spaces(), integer::<i64>());
+        assert_eq!(parse_all(parser(), "forward 5"), Ok(("forward", 5)));
+        assert_eq!(parse_all(parser(), "up  -3"), Ok(("up", -3)));
+        assert_eq!(parse_all(parser(), "5 up").unwrap_err().expected, "a word");
+    }
+
+    #[test]
+    fn preceded_and_map() {
+        let parser = map(preceded(literal("x="), integer::<i32>()), |x| x * 2);
+        assert_eq!(parse_all(parser, "x=21"), Ok(42));
+    }
+
+    #[test]
+    fn parse_all_rejects_leftovers() {
+        let err = parse_all(integer::<i64>(), "12abc").unwrap_err();
+        assert_eq!((err.col, err.expected.as_str()), (3, "end of input"));
+    }
+
+    #[test]
+    fn integer_reports_overflow() {
+        let err = parse_all(integer::<u8>(), "300").unwrap_err();
+        assert_eq!(err.expected, "an integer fitting in u8");
+    }
+
+    #[test]
+    fn parse_lines_reports_line_numbers() {
+        let lines = vec!["1".to_string(), "2".to_string(), "z".to_string()];
+        let err = parse_lines(integer::<i64>(), &lines).unwrap_err();
+        assert_eq!((err.line, err.col), (3, 1));
+    }
+
+    #[test]
+    fn integers_handles_signs() {
+        assert_eq!(integers::<i64>("x=-3..-1"), Ok(vec![-3, -1]));
+        assert_eq!(integers::<i64>("1-2"), Ok(vec![1, 2]));
+        assert_eq!(integers_array::<i64, 4>("0,9 -> 5,9"), Ok([0, 9, 5, 9]));
+        assert!(integers_array::<i64, 3>("1 2").is_err());
+
+        let lines = vec!["1 2".to_string(), "".to_string(), "3".to_string()];
+        assert_eq!(integers_in::<i64>(&lines), Ok(vec![1, 2, 3]));
+    }
+
+    #[test]
+    fn blocks_split_on_blank_lines() {
+        let lines: Vec<String> = ["a", "b", "", "  ", "c"].map(String::from).to_vec();
+        assert_eq!(blocks(&lines), vec![&lines[0..2], &lines[4..5]]);
+    }
+}
@@ -0,0 +1,524 @@
+//! Higher-dimensional counterparts to `XY`: the 3D `XYZ` and the N-dimensional `Point`.
+
+use std::fmt::{Display, Formatter};
+use std::ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+use std::str::FromStr;
+
+use crate::utils::{parse_components, ParseComponentsError, Separator, XY};
+
+/// Ordered by x, then y, then z.
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct XYZ<T: Eq> {
+    pub x: T,
+    pub y: T,
+    pub z: T,
+}
+
+impl<T: Eq> XYZ<T> {
+    pub fn new(x: T, y: T, z: T) -> Self {
+        XYZ { x, y, z }
+    }
+
+    /// Converts to another integer type, failing if any component does not fit.
+    #[allow(dead_code)]
+    pub fn try_cast<U: Eq + TryFrom<T>>(self) -> Result<XYZ<U>, U::Error> {
+        Ok(XYZ {
+            x: U::try_from(self.x)?,
+            y: U::try_from(self.y)?,
+            z: U::try_from(self.z)?,
+        })
+    }
+}
+
+impl<T: Display + Eq> Display for XYZ<T> {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "({}, {}, {})", self.x, self.y, self.z)
+    }
+}
+
+impl<T: FromStr + Copy + Eq> XYZ<T> {
+    pub fn parse_with(s: &str, separator: Separator) -> Result<Self, ParseComponentsError> {
+        match parse_components::<T>(s, 3, separator)?[..] {
+            [x, y, z] => Ok(XYZ { x, y, z }),
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<T: FromStr + Copy + Eq> FromStr for XYZ<T> {
+    type Err = ParseComponentsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        XYZ::parse_with(s, Separator::Comma)
+    }
+}
+
+impl<T: FromStr + Copy + Eq> TryFrom<&str> for XYZ<T> {
+    type Error = ParseComponentsError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl<T: Eq + Add<Output = T>> Add for XYZ<T> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        XYZ::new(self.x + rhs.x, self.y + rhs.y, self.z + rhs.z)
+    }
+}
+
+impl<T: Eq + AddAssign> AddAssign for XYZ<T> {
+    fn add_assign(&mut self, rhs: Self) {
+        self.x += rhs.x;
+        self.y += rhs.y;
+        self.z += rhs.z;
+    }
+}
+
+impl<T: Eq + Sub<Output = T>> Sub for XYZ<T> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        XYZ::new(self.x - rhs.x, self.y - rhs.y, self.z - rhs.z)
+    }
+}
+
+impl<T: Eq + SubAssign> SubAssign for XYZ<T> {
+    fn sub_assign(&mut self, rhs: Self) {
+        self.x -= rhs.x;
+        self.y -= rhs.y;
+        self.z -= rhs.z;
+    }
+}
+
+impl<T: Eq + Copy + Mul<Output = T>> Mul<T> for XYZ<T> {
+    type Output = Self;
+
+    fn mul(self, rhs: T) -> Self {
+        XYZ::new(self.x * rhs, self.y * rhs, self.z * rhs)
+    }
+}
+
+impl<T: Eq + Copy + MulAssign> MulAssign<T> for XYZ<T> {
+    fn mul_assign(&mut self, rhs: T) {
+        self.x *= rhs;
+        self.y *= rhs;
+        self.z *= rhs;
+    }
+}
+
+impl<T: Eq + Neg<Output = T>> Neg for XYZ<T> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        XYZ::new(-self.x, -self.y, -self.z)
+    }
+}
+
+macro_rules! impl_xyz_distances {
+    ($($t:ty),*) => {
+        $(
+            impl XYZ<$t> {
+                /// `None` if the distance does not fit in the component type.
+                #[allow(dead_code)]
+                pub fn manhattan(&self, other: &Self) -> Option<$t> {
+                    let dist = self
+                        .x
+                        .abs_diff(other.x)
+                        .checked_add(self.y.abs_diff(other.y))?
+                        .checked_add(self.z.abs_diff(other.z))?;
+                    <$t>::try_from(dist).ok()
+                }
+
+                /// `None` if the distance does not fit in the component type.
+                #[allow(dead_code)]
+                pub fn chebyshev(&self, other: &Self) -> Option<$t> {
+                    let dist = self
+                        .x
+                        .abs_diff(other.x)
+                        .max(self.y.abs_diff(other.y))
+                        .max(self.z.abs_diff(other.z));
+                    <$t>::try_from(dist).ok()
+                }
+            }
+        )*
+    };
+}
+
+impl_xyz_distances!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+macro_rules! impl_xyz_signed {
+    ($($t:ty),*) => {
+        $(
+            impl XYZ<$t> {
+                #[allow(dead_code)]
+                pub fn signum(&self) -> Self {
+                    XYZ::new(self.x.signum(), self.y.signum(), self.z.signum())
+                }
+
+                /// Quarter turn about the x axis, y towards z.
+                pub fn rotate_x(&self) -> Self {
+                    XYZ::new(self.x, -self.z, self.y)
+                }
+
+                /// Quarter turn about the y axis, z towards x.
+                #[allow(dead_code)]
+                pub fn rotate_y(&self) -> Self {
+                    XYZ::new(self.z, self.y, -self.x)
+                }
+
+                /// Quarter turn about the z axis, x towards y.
+                pub fn rotate_z(&self) -> Self {
+                    XYZ::new(-self.y, self.x, self.z)
+                }
+
+                /// This point under each of the 24 rotations of the cube, starting with the
+                /// identity. The order is the same for every point, so index `i` of two results
+                /// corresponds to the same rotation.
+                #[allow(dead_code)]
+                pub fn rotations(&self) -> Vec<Self> {
+                    // Alternating rolls and turns visits every orientation exactly once.
+                    let mut res = Vec::with_capacity(24);
+                    let mut cur = *self;
+                    for _ in 0..2 {
+                        for _ in 0..3 {
+                            cur = cur.rotate_x();
+                            res.push(cur);
+                            for _ in 0..3 {
+                                cur = cur.rotate_z();
+                                res.push(cur);
+                            }
+                        }
+                        cur = cur.rotate_x().rotate_z().rotate_x();
+                    }
+
+                    // The identity comes out 12th, so shift it to the front.
+                    res.rotate_left(11);
+                    res
+                }
+
+                /// The 6 face-adjacent points.
+                #[allow(dead_code)]
+                pub fn neighbours(&self) -> Vec<Self> {
+                    vec![
+                        XYZ::new(self.x - 1, self.y, self.z),
+                        XYZ::new(self.x + 1, self.y, self.z),
+                        XYZ::new(self.x, self.y - 1, self.z),
+                        XYZ::new(self.x, self.y + 1, self.z),
+                        XYZ::new(self.x, self.y, self.z - 1),
+                        XYZ::new(self.x, self.y, self.z + 1),
+                    ]
+                }
+
+                /// The 26 points sharing a face, edge or corner.
+                #[allow(dead_code)]
+                pub fn neighbours_all(&self) -> Vec<Self> {
+                    let mut res = Vec::with_capacity(26);
+                    for dx in -1..=1 {
+                        for dy in -1..=1 {
+                            for dz in -1..=1 {
+                                if (dx, dy, dz) != (0, 0, 0) {
+                                    res.push(XYZ::new(self.x + dx, self.y + dy, self.z + dz));
+                                }
+                            }
+                        }
+                    }
+
+                    res
+                }
+            }
+        )*
+    };
+}
+
+impl_xyz_signed!(i8, i16, i32, i64, i128, isize);
+
+/// Point with a compile-time number of `i64` coordinates.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Point<const N: usize> {
+    pub coords: [i64; N],
+}
+
+impl<const N: usize> Point<N> {
+    pub fn new(coords: [i64; N]) -> Self {
+        Point { coords }
+    }
+
+    #[allow(dead_code)]
+    pub fn origin() -> Self {
+        Point { coords: [0; N] }
+    }
+
+    /// `None` if the distance does not fit in an `i64`.
+    #[allow(dead_code)]
+    pub fn manhattan(&self, other: &Self) -> Option<i64> {
+        let dist = self
+            .coords
+            .iter()
+            .zip(other.coords.iter())
+            .try_fold(0u64, |sum, (lhs, rhs)| sum.checked_add(lhs.abs_diff(*rhs)))?;
+        i64::try_from(dist).ok()
+    }
+
+    /// `None` if the distance does not fit in an `i64`.
+    #[allow(dead_code)]
+    pub fn chebyshev(&self, other: &Self) -> Option<i64> {
+        let dist = self
+            .coords
+            .iter()
+            .zip(other.coords.iter())
+            .map(|(lhs, rhs)| lhs.abs_diff(*rhs))
+            .max()
+            .unwrap_or(0);
+        i64::try_from(dist).ok()
+    }
+
+    #[allow(dead_code)]
+    pub fn signum(&self) -> Self {
+        Point {
+            coords: self.coords.map(i64::signum),
+        }
+    }
+
+    /// The 2N points one step away along a single axis.
+    #[allow(dead_code)]
+    pub fn neighbours(&self) -> Vec<Self> {
+        let mut res = Vec::with_capacity(2 * N);
+        for axis in 0..N {
+            for delta in [-1, 1] {
+                let mut coords = self.coords;
+                coords[axis] += delta;
+                res.push(Point { coords });
+            }
+        }
+
+        res
+    }
+
+    /// The 3^N - 1 points at a Chebyshev distance of 1.
+    #[allow(dead_code)]
+    pub fn neighbours_all(&self) -> Vec<Self> {
+        let mut res = vec![*self];
+        for axis in 0..N {
+            res = res
+                .into_iter()
+                .flat_map(|point| {
+                    [-1, 0, 1].map(|delta| {
+                        let mut coords = point.coords;
+                        coords[axis] += delta;
+                        Point { coords }
+                    })
+                })
+                .collect();
+        }
+
+        res.retain(|point| point != self);
+        res
+    }
+}
+
+impl<const N: usize> Display for Point<N> {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "({})",
+            self.coords
+                .iter()
+                .map(i64::to_string)
+                .collect::<Vec<String>>()
+                .join(", ")
+        )
+    }
+}
+
+impl<const N: usize> Point<N> {
+    pub fn parse_with(s: &str, separator: Separator) -> Result<Self, ParseComponentsError> {
+        let mut coords = [0; N];
+        coords.copy_from_slice(&parse_components::<i64>(s, N, separator)?);
+        Ok(Point { coords })
+    }
+}
+
+impl<const N: usize> FromStr for Point<N> {
+    type Err = ParseComponentsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Point::parse_with(s, Separator::Comma)
+    }
+}
+
+impl<const N: usize> TryFrom<&str> for Point<N> {
+    type Error = ParseComponentsError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<XY<i64>> for Point<2> {
+    fn from(xy: XY<i64>) -> Self {
+        Point::new([xy.x, xy.y])
+    }
+}
+
+impl From<XYZ<i64>> for Point<3> {
+    fn from(xyz: XYZ<i64>) -> Self {
+        Point::new([xyz.x, xyz.y, xyz.z])
+    }
+}
+
+impl<const N: usize> Add for Point<N> {
+    type Output = Self;
+
+    fn add(mut self, rhs: Self) -> Self {
+        self += rhs;
+        self
+    }
+}
+
+impl<const N: usize> AddAssign for Point<N> {
+    fn add_assign(&mut self, rhs: Self) {
+        for (lhs, rhs) in self.coords.iter_mut().zip(rhs.coords) {
+            *lhs += rhs;
+        }
+    }
+}
+
+impl<const N: usize> Sub for Point<N> {
+    type Output = Self;
+
+    fn sub(mut self, rhs: Self) -> Self {
+        self -= rhs;
+        self
+    }
+}
+
+impl<const N: usize> SubAssign for Point<N> {
+    fn sub_assign(&mut self, rhs: Self) {
+        for (lhs, rhs) in self.coords.iter_mut().zip(rhs.coords) {
+            *lhs -= rhs;
+        }
+    }
+}
+
+impl<const N: usize> Mul<i64> for Point<N> {
+    type Output = Self;
+
+    fn mul(mut self, rhs: i64) -> Self {
+        self *= rhs;
+        self
+    }
+}
+
+impl<const N: usize> MulAssign<i64> for Point<N> {
+    fn mul_assign(&mut self, rhs: i64) {
+        for lhs in self.coords.iter_mut() {
+            *lhs *= rhs;
+        }
+    }
+}
+
+impl<const N: usize> Neg for Point<N> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Point {
+            coords: self.coords.map(|val| -val),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn xyz_arithmetic_and_distances() {
+        let a = XYZ::new(1i64, -2, 3);
+        let b = XYZ::new(-4i64, 0, 5);
+        assert_eq!(a + b, XYZ::new(-3, -2, 8));
+        assert_eq!(a - b, XYZ::new(5, -2, -2));
+        assert_eq!(-a * 2, XYZ::new(-2, 4, -6));
+        assert_eq!((a - b).signum(), XYZ::new(1, -1, -1));
+        assert_eq!(a.manhattan(&b), Some(9));
+        assert_eq!(a.chebyshev(&b), Some(5));
+
+        let far = XYZ::new(i64::MAX, 0, 0);
+        assert_eq!(far.manhattan(&XYZ::new(-1, 0, 0)), None);
+        assert_eq!(XYZ::new(0u8, 0, 0).manhattan(&XYZ::new(200, 100, 0)), None);
+        assert!(a.try_cast::<u8>().is_err());
+        assert_eq!(a.try_cast::<i8>(), Ok(XYZ::new(1i8, -2, 3)));
+    }
+
+    #[test]
+    fn xyz_parsing() {
+        assert_eq!("1, -2,3".parse::<XYZ<i64>>(), Ok(XYZ::new(1, -2, 3)));
+        assert_eq!(
+            XYZ::<i64>::parse_with("1x2x3", Separator::X),
+            Ok(XYZ::new(1, 2, 3))
+        );
+        assert!(XYZ::<i64>::try_from("1,2").is_err());
+        assert_eq!(XYZ::new(1, 2, 3).to_string(), "(1, 2, 3)");
+    }
+
+    #[test]
+    fn xyz_rotations_are_distinct() {
+        let point = XYZ::new(1i64, 2, 3);
+        let rotations = point.rotations();
+        assert_eq!(rotations[0], point);
+        assert_eq!(rotations.iter().collect::<HashSet<_>>().len(), 24);
+        assert!(rotations
+            .iter()
+            .all(|rotated| rotated.manhattan(&XYZ::new(0, 0, 0)) == Some(6)));
+
+        assert_eq!(point.rotate_x().rotate_x().rotate_x().rotate_x(), point);
+        assert_eq!(point.rotate_y(), XYZ::new(3, 2, -1));
+        assert_eq!(point.rotate_z(), XYZ::new(-2, 1, 3));
+    }
+
+    #[test]
+    fn xyz_neighbours() {
+        let point = XYZ::new(0i64, 0, 0);
+        assert_eq!(point.neighbours().len(), 6);
+        assert!(point
+            .neighbours()
+            .iter()
+            .all(|next| next.manhattan(&point) == Some(1)));
+        assert_eq!(point.neighbours_all().len(), 26);
+        assert!(!point.neighbours_all().contains(&point));
+    }
+
+    #[test]
+    fn point_matches_xy_and_xyz() {
+        let a: Point<3> = XYZ::new(1, -2, 3).into();
+        let b: Point<3> = "-4,0,5".parse().unwrap();
+        assert_eq!(a.manhattan(&b), Some(9));
+        assert_eq!(a.chebyshev(&b), Some(5));
+        assert_eq!(a + b, Point::new([-3, -2, 8]));
+        assert_eq!(-(a - b) * 2, Point::new([-10, 4, 4]));
+        assert_eq!((a - b).signum(), Point::new([1, -1, -1]));
+        assert_eq!(a.to_string(), "(1, -2, 3)");
+
+        let xy: Point<2> = XY::new(3, 4).into();
+        assert_eq!(xy.manhattan(&Point::origin()), Some(7));
+        assert_eq!(Point::<2>::parse_with("3..4", Separator::DotDot), Ok(xy));
+        assert!(Point::<2>::try_from("1,2,3").is_err());
+        assert_eq!(
+            Point::new([i64::MIN]).manhattan(&Point::new([i64::MAX])),
+            None
+        );
+    }
+
+    #[test]
+    fn point_neighbours() {
+        let origin = Point::<4>::origin();
+        assert_eq!(origin.neighbours().len(), 8);
+        let all = origin.neighbours_all();
+        assert_eq!(all.len(), 80);
+        assert!(all.iter().all(|next| next.chebyshev(&origin) == Some(1)));
+    }
+}
@@ -0,0 +1,65 @@
+//! Seeded pseudo-random numbers for reproducible scrambling and test fixtures. Not suitable
+//! for anything that needs real randomness.
+
+/// Xorshift64 generator. The same seed always gives the same sequence.
+#[derive(Clone, Debug)]
+pub struct XorShift {
+    state: u64,
+}
+
+impl XorShift {
+    /// A seed of 0 is treated as 1, since xorshift never leaves the all-zero state.
+    pub fn new(seed: u64) -> Self {
+        XorShift { state: seed.max(1) }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    /// Value in `0..bound`, slightly biased towards small values unless `bound` is a power of
+    /// two. Panics if `bound` is zero.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        self.next_u64() % bound
+    }
+
+    /// Fisher-Yates shuffle.
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for idx in (1..items.len()).rev() {
+            items.swap(idx, self.below(idx as u64 + 1) as usize);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seeds_are_reproducible() {
+        let first = (0..5)
+            .scan(XorShift::new(7), |rng, _| Some(rng.next_u64()))
+            .collect::<Vec<u64>>();
+        let second = (0..5)
+            .scan(XorShift::new(7), |rng, _| Some(rng.next_u64()))
+            .collect::<Vec<u64>>();
+        assert_eq!(first, second);
+        assert_ne!(XorShift::new(8).next_u64(), first[0]);
+        assert_eq!(XorShift::new(0).next_u64(), XorShift::new(1).next_u64());
+    }
+
+    #[test]
+    fn bounded_values_and_shuffles() {
+        let mut rng = XorShift::new(42);
+        assert!((0..1000).all(|_| rng.below(10) < 10));
+
+        let mut items = (0..20).collect::<Vec<i32>>();
+        rng.shuffle(&mut items);
+        assert_ne!(items, (0..20).collect::<Vec<i32>>());
+        items.sort_unstable();
+        assert_eq!(items, (0..20).collect::<Vec<i32>>());
+    }
+}
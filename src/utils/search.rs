@@ -0,0 +1,407 @@
+//! Graph search primitives: iterative flood fills, connected components and
+//! shortest paths (Dijkstra / A*) over anything implementing [`Graph`].
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use std::hash::Hash;
+
+pub trait Graph {
+    type Node: Copy + Eq + Hash;
+
+    /// All nodes directly reachable from `node`, before any filtering by the search.
+    fn neighbours(&self, node: Self::Node) -> Vec<Self::Node>;
+}
+
+/// Result of a weighted search: the nodes visited from start to goal (inclusive) and the total
+/// cost of the path.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Path<N> {
+    pub nodes: Vec<N>,
+    pub cost: i64,
+}
+
+/// Result of labelling the connected components of a graph.
+#[derive(Debug)]
+pub struct Components<N> {
+    // Mapping of {node => index into members}.
+    #[allow(dead_code)]
+    pub labels: HashMap<N, usize>,
+    pub members: Vec<Vec<N>>,
+}
+
+impl<N> Components<N> {
+    pub fn sizes(&self) -> Vec<usize> {
+        self.members.iter().map(Vec::len).collect()
+    }
+}
+
+/// All nodes reachable from `start` through nodes accepted by `passable`, in BFS order.
+#[allow(dead_code)]
+pub fn bfs_fill<G: Graph>(
+    graph: &G,
+    start: G::Node,
+    passable: impl Fn(G::Node) -> bool,
+) -> Vec<G::Node> {
+    let mut seen: HashSet<G::Node> = HashSet::new();
+    bfs_fill_with(graph, start, &passable, &mut seen)
+}
+
+fn bfs_fill_with<G: Graph>(
+    graph: &G,
+    start: G::Node,
+    passable: &impl Fn(G::Node) -> bool,
+    seen: &mut HashSet<G::Node>,
+) -> Vec<G::Node> {
+    let mut res = vec![];
+    if !passable(start) || !seen.insert(start) {
+        return res;
+    }
+
+    let mut queue: VecDeque<G::Node> = VecDeque::from([start]);
+    while let Some(node) = queue.pop_front() {
+        res.push(node);
+        for next in graph.neighbours(node) {
+            if passable(next) && seen.insert(next) {
+                queue.push_back(next);
+            }
+        }
+    }
+
+    res
+}
+
+/// All nodes reachable from `start` through nodes accepted by `passable`, in DFS order.
+#[allow(dead_code)]
+pub fn dfs_fill<G: Graph>(
+    graph: &G,
+    start: G::Node,
+    passable: impl Fn(G::Node) -> bool,
+) -> Vec<G::Node> {
+    let mut res = vec![];
+    let mut seen: HashSet<G::Node> = HashSet::new();
+    let mut stack: Vec<G::Node> = vec![start];
+    while let Some(node) = stack.pop() {
+        if !passable(node) || !seen.insert(node) {
+            continue;
+        }
+
+        res.push(node);
+        // Reversed so that the first neighbour is visited first.
+        for next in graph.neighbours(node).into_iter().rev() {
+            if !seen.contains(&next) {
+                stack.push(next);
+            }
+        }
+    }
+
+    res
+}
+
+/// Unweighted distances from `start` to every reachable node.
+#[allow(dead_code)]
+pub fn bfs_distances<G: Graph>(
+    graph: &G,
+    start: G::Node,
+    passable: impl Fn(G::Node) -> bool,
+) -> HashMap<G::Node, usize> {
+    let mut dist: HashMap<G::Node, usize> = HashMap::new();
+    if !passable(start) {
+        return dist;
+    }
+
+    dist.insert(start, 0);
+    let mut queue: VecDeque<G::Node> = VecDeque::from([start]);
+    while let Some(node) = queue.pop_front() {
+        let next_dist = dist[&node] + 1;
+        for next in graph.neighbours(node) {
+            if passable(next) && !dist.contains_key(&next) {
+                dist.insert(next, next_dist);
+                queue.push_back(next);
+            }
+        }
+    }
+
+    dist
+}
+
+/// Labels every passable node in `nodes` with the index of the component it belongs to.
+/// Components are numbered in the order their first node appears in `nodes`.
+pub fn connected_components<G: Graph>(
+    graph: &G,
+    nodes: impl IntoIterator<Item = G::Node>,
+    passable: impl Fn(G::Node) -> bool,
+) -> Components<G::Node> {
+    let mut seen: HashSet<G::Node> = HashSet::new();
+    let mut labels: HashMap<G::Node, usize> = HashMap::new();
+    let mut members: Vec<Vec<G::Node>> = vec![];
+    for node in nodes {
+        let component = bfs_fill_with(graph, node, &passable, &mut seen);
+        if component.is_empty() {
+            continue;
+        }
+
+        for member in &component {
+            labels.insert(*member, members.len());
+        }
+        members.push(component);
+    }
+
+    Components { labels, members }
+}
+
+struct State<N> {
+    priority: i64,
+    cost: i64,
+    node: N,
+}
+
+impl<N> PartialEq for State<N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.priority == other.priority
+    }
+}
+
+impl<N> Eq for State<N> {}
+
+impl<N> PartialOrd for State<N> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<N> Ord for State<N> {
+    // Reversed so that BinaryHeap pops the lowest priority first.
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.priority.cmp(&self.priority)
+    }
+}
+
+/// Shortest-path tree produced by [`dijkstra_all`].
+#[derive(Debug)]
+pub struct ShortestPaths<N> {
+    pub start: N,
+    pub distances: HashMap<N, i64>,
+    // Mapping of {node => node it was reached from}.
+    pub previous: HashMap<N, N>,
+}
+
+impl<N: Copy + Eq + Hash> ShortestPaths<N> {
+    pub fn distance_to(&self, node: N) -> Option<i64> {
+        self.distances.get(&node).copied()
+    }
+
+    pub fn path_to(&self, node: N) -> Option<Path<N>> {
+        let cost = self.distance_to(node)?;
+        Some(Path {
+            nodes: walk_back(&self.previous, self.start, node),
+            cost,
+        })
+    }
+}
+
+fn walk_back<N: Copy + Eq + Hash>(previous: &HashMap<N, N>, start: N, end: N) -> Vec<N> {
+    let mut nodes = vec![end];
+    let mut cur = end;
+    while cur != start {
+        cur = previous[&cur];
+        nodes.push(cur);
+    }
+
+    nodes.reverse();
+    nodes
+}
+
+/// Dijkstra from `start` over the whole reachable graph. `cost(from, to)` gives the weight of an
+/// edge, or `None` if it cannot be taken. Weights must be non-negative.
+#[allow(dead_code)]
+pub fn dijkstra_all<G: Graph>(
+    graph: &G,
+    start: G::Node,
+    cost: impl Fn(G::Node, G::Node) -> Option<i64>,
+) -> ShortestPaths<G::Node> {
+    let (_, paths) = best_first(graph, start, |_| false, cost, |_| 0);
+    paths
+}
+
+/// Cheapest path from `start` to the first node accepted by `is_goal`.
+#[allow(dead_code)]
+pub fn dijkstra<G: Graph>(
+    graph: &G,
+    start: G::Node,
+    is_goal: impl Fn(G::Node) -> bool,
+    cost: impl Fn(G::Node, G::Node) -> Option<i64>,
+) -> Option<Path<G::Node>> {
+    let (goal, paths) = best_first(graph, start, is_goal, cost, |_| 0);
+    paths.path_to(goal?)
+}
+
+/// A* from `start` to `goal`. `heuristic` must never overestimate the remaining cost, otherwise
+/// the returned path may not be the cheapest one.
+#[allow(dead_code)]
+pub fn astar<G: Graph>(
+    graph: &G,
+    start: G::Node,
+    goal: G::Node,
+    cost: impl Fn(G::Node, G::Node) -> Option<i64>,
+    heuristic: impl Fn(G::Node) -> i64,
+) -> Option<Path<G::Node>> {
+    let (goal, paths) = best_first(graph, start, |node| node == goal, cost, heuristic);
+    paths.path_to(goal?)
+}
+
+/// Shared search loop. Stops at the first node accepted by `is_goal` and returns it alongside
+/// the search tree explored so far.
+fn best_first<G: Graph>(
+    graph: &G,
+    start: G::Node,
+    is_goal: impl Fn(G::Node) -> bool,
+    cost: impl Fn(G::Node, G::Node) -> Option<i64>,
+    heuristic: impl Fn(G::Node) -> i64,
+) -> (Option<G::Node>, ShortestPaths<G::Node>) {
+    let mut distances: HashMap<G::Node, i64> = HashMap::from([(start, 0)]);
+    let mut previous: HashMap<G::Node, G::Node> = HashMap::new();
+    let mut heap = BinaryHeap::from([State {
+        priority: heuristic(start),
+        cost: 0,
+        node: start,
+    }]);
+
+    let mut goal = None;
+    while let Some(State {
+        cost: dist, node, ..
+    }) = heap.pop()
+    {
+        if dist > distances[&node] {
+            continue;
+        }
+
+        if is_goal(node) {
+            goal = Some(node);
+            break;
+        }
+
+        for next in graph.neighbours(node) {
+            if let Some(weight) = cost(node, next) {
+                let next_dist = dist + weight;
+                if distances.get(&next).is_none_or(|&d| next_dist < d) {
+                    distances.insert(next, next_dist);
+                    previous.insert(next, node);
+                    heap.push(State {
+                        priority: next_dist + heuristic(next),
+                        cost: next_dist,
+                        node: next,
+                    });
+                }
+            }
+        }
+    }
+
+    (
+        goal,
+        ShortestPaths {
+            start,
+            distances,
+            previous,
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::grid::Grid;
+
+    // 0 marks a wall.
+    fn maze() -> Grid {
+        Grid::new(vec![
+            vec![1, 1, 1, 0, 1],
+            vec![0, 0, 1, 0, 1],
+            vec![1, 1, 9, 1, 1],
+            vec![1, 0, 0, 0, 0],
+        ])
+    }
+
+    #[test]
+    fn fills_reach_the_same_nodes() {
+        let grid = maze();
+        let open = |(row, col)| grid.at(row, col) != Some(0);
+        let bfs = bfs_fill(&grid, (0, 0), open);
+        let dfs = dfs_fill(&grid, (0, 0), open);
+        assert_eq!(bfs.len(), 12);
+        assert_eq!(bfs[..3], [(0, 0), (0, 1), (0, 2)]);
+        assert_eq!(
+            bfs.iter().collect::<HashSet<_>>(),
+            dfs.iter().collect::<HashSet<_>>()
+        );
+
+        assert!(bfs_fill(&grid, (1, 0), open).is_empty());
+        assert!(dfs_fill(&grid, (1, 0), open).is_empty());
+    }
+
+    #[test]
+    fn dfs_goes_deep_first() {
+        let grid = Grid::new(vec![vec![1; 3]; 2]);
+        // Neighbours are up, down, left, right, so from the corner it heads down first.
+        assert_eq!(
+            dfs_fill(&grid, (0, 0), |_| true),
+            vec![(0, 0), (1, 0), (1, 1), (0, 1), (0, 2), (1, 2)]
+        );
+    }
+
+    #[test]
+    fn bfs_distances_count_steps() {
+        let grid = maze();
+        let dist = bfs_distances(&grid, (0, 0), |(row, col)| grid.at(row, col) != Some(0));
+        assert_eq!(dist[&(0, 0)], 0);
+        assert_eq!(dist[&(2, 2)], 4);
+        assert_eq!(dist[&(0, 4)], 8);
+        assert_eq!(dist.get(&(1, 0)), None);
+    }
+
+    #[test]
+    fn components_are_labelled_in_order() {
+        let grid = maze();
+        let components = connected_components(&grid, grid.positions(), |(row, col)| {
+            grid.at(row, col) != Some(0)
+        });
+        assert_eq!(components.sizes(), vec![12]);
+
+        let components = connected_components(&grid, grid.positions(), |(row, col)| {
+            grid.at(row, col) == Some(1)
+        });
+        assert_eq!(components.sizes(), vec![4, 4, 3]);
+        assert_eq!(components.labels[&(0, 0)], 0);
+        assert_eq!(components.labels[&(0, 4)], 1);
+        assert_eq!(components.labels[&(3, 0)], 2);
+        assert_eq!(components.labels.get(&(2, 2)), None);
+    }
+
+    #[test]
+    fn weighted_searches_avoid_expensive_cells() {
+        let grid = maze();
+        let cost = |_, (row, col)| grid.at(row, col).filter(|&weight| weight > 0);
+
+        let path = dijkstra(&grid, (0, 0), |node| node == (0, 4), cost).unwrap();
+        assert_eq!(path.cost, 16);
+        assert_eq!(path.nodes.first(), Some(&(0, 0)));
+        assert_eq!(path.nodes.last(), Some(&(0, 4)));
+
+        let manhattan = |(row, col): (i64, i64)| row.abs() + (col - 4).abs();
+        assert_eq!(astar(&grid, (0, 0), (0, 4), cost, manhattan), Some(path));
+        assert_eq!(dijkstra(&grid, (0, 0), |node| node == (1, 0), cost), None);
+    }
+
+    #[test]
+    fn dijkstra_all_builds_a_tree() {
+        let grid = maze();
+        let paths = dijkstra_all(&grid, (0, 0), |_, (row, col)| {
+            grid.at(row, col).filter(|&weight| weight > 0)
+        });
+        assert_eq!(paths.distance_to((3, 0)), Some(15));
+        assert_eq!(
+            paths.path_to((2, 0)).unwrap().nodes,
+            vec![(0, 0), (0, 1), (0, 2), (1, 2), (2, 2), (2, 1), (2, 0)]
+        );
+        assert_eq!(paths.path_to((1, 1)), None);
+    }
+}
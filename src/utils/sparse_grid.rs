@@ -0,0 +1,227 @@
+//! Unbounded 2D grid that only stores the cells which have been written to.
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+use crate::utils::search::Graph;
+use crate::utils::XY;
+
+#[derive(Clone, Debug)]
+pub struct SparseGrid<T> {
+    cells: HashMap<XY<i64>, T>,
+    // Value reported for any cell that has not been written.
+    default: T,
+    // Inclusive (min, max) corners of every cell written so far.
+    bounds: Option<(XY<i64>, XY<i64>)>,
+}
+
+impl<T: Default> Default for SparseGrid<T> {
+    fn default() -> Self {
+        SparseGrid::new(T::default())
+    }
+}
+
+/// `bounds` extended to cover `xy`.
+fn grown(bounds: Option<(XY<i64>, XY<i64>)>, xy: XY<i64>) -> (XY<i64>, XY<i64>) {
+    match bounds {
+        None => (xy, xy),
+        Some((min, max)) => (
+            XY::new(min.x.min(xy.x), min.y.min(xy.y)),
+            XY::new(max.x.max(xy.x), max.y.max(xy.y)),
+        ),
+    }
+}
+
+impl<T> SparseGrid<T> {
+    pub fn new(default: T) -> Self {
+        SparseGrid {
+            cells: HashMap::new(),
+            default,
+            bounds: None,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// Whether the cell has been written, as opposed to holding the default.
+    #[allow(dead_code)]
+    pub fn contains(&self, xy: &XY<i64>) -> bool {
+        self.cells.contains_key(xy)
+    }
+
+    pub fn get(&self, xy: &XY<i64>) -> &T {
+        self.cells.get(xy).unwrap_or(&self.default)
+    }
+
+    pub fn insert(&mut self, xy: XY<i64>, value: T) -> Option<T> {
+        self.grow_bounds(xy);
+        self.cells.insert(xy, value)
+    }
+
+    #[allow(dead_code)]
+    pub fn remove(&mut self, xy: &XY<i64>) -> Option<T> {
+        let res = self.cells.remove(xy);
+        if res.is_some() {
+            self.bounds = self
+                .cells
+                .keys()
+                .fold(None, |bounds, &xy| Some(grown(bounds, xy)));
+        }
+
+        res
+    }
+
+    /// Inclusive (min, max) corners of the written cells, or `None` if nothing was written.
+    #[allow(dead_code)]
+    pub fn bounds(&self) -> Option<(XY<i64>, XY<i64>)> {
+        self.bounds
+    }
+
+    fn grow_bounds(&mut self, xy: XY<i64>) {
+        self.bounds = Some(grown(self.bounds, xy));
+    }
+
+    /// Written cells in row-major order, i.e. sorted by y and then x.
+    #[allow(dead_code)]
+    pub fn iter(&self) -> impl Iterator<Item = (XY<i64>, &T)> {
+        let mut keys = self.cells.keys().copied().collect::<Vec<XY<i64>>>();
+        keys.sort_unstable_by_key(|xy| (xy.y, xy.x));
+        keys.into_iter().map(move |xy| (xy, &self.cells[&xy]))
+    }
+
+    /// Written values in arbitrary order.
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.cells.values()
+    }
+
+    /// All positions within the bounding box in row-major order, written or not.
+    #[allow(dead_code)]
+    pub fn positions(&self) -> impl Iterator<Item = XY<i64>> {
+        let (min, max) = match self.bounds {
+            Some(bounds) => bounds,
+            None => (XY::new(0, 0), XY::new(-1, -1)),
+        };
+        (min.y..=max.y).flat_map(move |y| (min.x..=max.x).map(move |x| XY::new(x, y)))
+    }
+}
+
+impl<T: Clone> SparseGrid<T> {
+    /// Mutable access to a cell, writing the default into it first if needed.
+    pub fn get_mut(&mut self, xy: XY<i64>) -> &mut T {
+        self.grow_bounds(xy);
+        self.cells.entry(xy).or_insert_with(|| self.default.clone())
+    }
+}
+
+impl<T: Default> FromIterator<(XY<i64>, T)> for SparseGrid<T> {
+    fn from_iter<I: IntoIterator<Item = (XY<i64>, T)>>(iter: I) -> Self {
+        let mut res = SparseGrid::default();
+        for (xy, value) in iter {
+            res.insert(xy, value);
+        }
+
+        res
+    }
+}
+
+impl<T> Graph for SparseGrid<T> {
+    type Node = XY<i64>;
+
+    /// The grid is unbounded, so every cell has all four neighbours. Searches should restrict
+    /// themselves through their `passable` predicate.
+    fn neighbours(&self, xy: XY<i64>) -> Vec<XY<i64>> {
+        vec![
+            XY::new(xy.x, xy.y - 1),
+            XY::new(xy.x, xy.y + 1),
+            XY::new(xy.x - 1, xy.y),
+            XY::new(xy.x + 1, xy.y),
+        ]
+    }
+}
+
+impl<T: Display> Display for SparseGrid<T> {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        let (min, max) = match self.bounds {
+            Some(bounds) => bounds,
+            None => return writeln!(f),
+        };
+
+        writeln!(
+            f,
+            "{}",
+            (min.y..=max.y)
+                .map(|y| (min.x..=max.x)
+                    .map(|x| self.get(&XY::new(x, y)).to_string())
+                    .collect::<Vec<String>>()
+                    .join(" "))
+                .collect::<Vec<String>>()
+                .join("\n")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::search::bfs_fill;
+
+    #[test]
+    fn unwritten_cells_hold_default() {
+        let mut grid = SparseGrid::new(7);
+        assert!(grid.is_empty());
+        assert_eq!(grid.bounds(), None);
+        assert_eq!(*grid.get(&XY::new(-5, 3)), 7);
+
+        *grid.get_mut(XY::new(-5, 3)) += 1;
+        assert_eq!(*grid.get(&XY::new(-5, 3)), 8);
+        assert!(grid.contains(&XY::new(-5, 3)));
+        assert_eq!(grid.len(), 1);
+    }
+
+    #[test]
+    fn bounds_grow_and_shrink() {
+        let mut grid: SparseGrid<i64> = [(XY::new(1, 2), 1), (XY::new(-3, 4), 2)]
+            .into_iter()
+            .collect();
+        assert_eq!(grid.bounds(), Some((XY::new(-3, 2), XY::new(1, 4))));
+
+        assert_eq!(grid.remove(&XY::new(-3, 4)), Some(2));
+        assert_eq!(grid.bounds(), Some((XY::new(1, 2), XY::new(1, 2))));
+        assert_eq!(grid.remove(&XY::new(1, 2)), Some(1));
+        assert_eq!(grid.bounds(), None);
+    }
+
+    #[test]
+    fn iterates_in_row_major_order() {
+        let grid: SparseGrid<i64> = [(XY::new(1, 1), 4), (XY::new(0, 1), 3), (XY::new(1, 0), 2)]
+            .into_iter()
+            .collect();
+        let cells = grid.iter().map(|(xy, &v)| (xy, v)).collect::<Vec<_>>();
+        assert_eq!(
+            cells,
+            vec![(XY::new(1, 0), 2), (XY::new(0, 1), 3), (XY::new(1, 1), 4)]
+        );
+        assert_eq!(
+            grid.positions().collect::<Vec<_>>(),
+            vec![XY::new(0, 0), XY::new(1, 0), XY::new(0, 1), XY::new(1, 1)]
+        );
+        assert_eq!(grid.to_string(), "0 2\n3 4\n");
+    }
+
+    #[test]
+    fn searches_are_limited_by_passable() {
+        let grid: SparseGrid<i64> = SparseGrid::new(0);
+        let filled = bfs_fill(&grid, XY::new(0, 0), |xy| {
+            xy.x.abs() <= 1 && xy.y.abs() <= 1
+        });
+        assert_eq!(filled.len(), 9);
+    }
+}
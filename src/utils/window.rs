@@ -0,0 +1,416 @@
+//! Fixed-size sliding windows that keep a running aggregate of their contents.
+//!
+//! [`SlidingWindow`] works for any [`Monoid`] in amortised O(1) per push using a two-stack
+//! queue, so the operation need not be invertible. [`MonotonicWindow`] specialises min/max with
+//! a monotonic deque.
+
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+use std::ops::{Add, Mul};
+
+pub trait Monoid {
+    type Item: Clone;
+
+    fn identity() -> Self::Item;
+
+    /// Must be associative. Arguments are always given oldest first.
+    fn combine(lhs: &Self::Item, rhs: &Self::Item) -> Self::Item;
+}
+
+pub struct Sum<T>(PhantomData<T>);
+
+impl<T: Copy + Default + Add<Output = T>> Monoid for Sum<T> {
+    type Item = T;
+
+    fn identity() -> T {
+        T::default()
+    }
+
+    fn combine(lhs: &T, rhs: &T) -> T {
+        *lhs + *rhs
+    }
+}
+
+#[allow(dead_code)]
+pub struct Product<T>(PhantomData<T>);
+
+impl<T: Copy + From<u8> + Mul<Output = T>> Monoid for Product<T> {
+    type Item = T;
+
+    fn identity() -> T {
+        T::from(1)
+    }
+
+    fn combine(lhs: &T, rhs: &T) -> T {
+        *lhs * *rhs
+    }
+}
+
+/// Min over `Option<T>`, where `None` is the empty window.
+#[allow(dead_code)]
+pub struct Min<T>(PhantomData<T>);
+
+impl<T: Copy + Ord> Monoid for Min<T> {
+    type Item = Option<T>;
+
+    fn identity() -> Option<T> {
+        None
+    }
+
+    fn combine(lhs: &Option<T>, rhs: &Option<T>) -> Option<T> {
+        match (lhs, rhs) {
+            (Some(lhs), Some(rhs)) => Some(*lhs.min(rhs)),
+            _ => lhs.or(*rhs),
+        }
+    }
+}
+
+/// Max over `Option<T>`, where `None` is the empty window.
+#[allow(dead_code)]
+pub struct Max<T>(PhantomData<T>);
+
+impl<T: Copy + Ord> Monoid for Max<T> {
+    type Item = Option<T>;
+
+    fn identity() -> Option<T> {
+        None
+    }
+
+    fn combine(lhs: &Option<T>, rhs: &Option<T>) -> Option<T> {
+        match (lhs, rhs) {
+            (Some(lhs), Some(rhs)) => Some(*lhs.max(rhs)),
+            _ => lhs.or(*rhs),
+        }
+    }
+}
+
+pub struct SlidingWindow<M: Monoid> {
+    size: usize,
+    // Oldest items, oldest on top. Each entry holds the aggregate of itself and every entry
+    // below it, i.e. everything from it to the newest item in `front`.
+    front: Vec<(M::Item, M::Item)>,
+    // Newest items, oldest first, and their aggregate.
+    back: Vec<M::Item>,
+    back_aggregate: M::Item,
+}
+
+impl<M: Monoid> SlidingWindow<M> {
+    pub fn new(size: usize) -> Self {
+        assert!(size > 0, "Window size must be positive");
+        SlidingWindow {
+            size,
+            front: Vec::with_capacity(size),
+            back: Vec::with_capacity(size),
+            back_aggregate: M::identity(),
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn len(&self) -> usize {
+        self.front.len() + self.back.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len() == self.size
+    }
+
+    /// Adds an item, returning the oldest one if it had to be evicted to make room.
+    pub fn push(&mut self, item: M::Item) -> Option<M::Item> {
+        let evicted = if self.is_full() {
+            self.pop_oldest()
+        } else {
+            None
+        };
+
+        self.back_aggregate = M::combine(&self.back_aggregate, &item);
+        self.back.push(item);
+        evicted
+    }
+
+    fn pop_oldest(&mut self) -> Option<M::Item> {
+        if self.front.is_empty() {
+            // Move everything over so that the oldest item ends up on top.
+            let mut aggregate = M::identity();
+            while let Some(item) = self.back.pop() {
+                aggregate = M::combine(&item, &aggregate);
+                self.front.push((item, aggregate.clone()));
+            }
+            self.back_aggregate = M::identity();
+        }
+
+        self.front.pop().map(|(item, _)| item)
+    }
+
+    /// Aggregate of everything currently in the window, oldest first.
+    pub fn aggregate(&self) -> M::Item {
+        match self.front.last() {
+            Some((_, front_aggregate)) => M::combine(front_aggregate, &self.back_aggregate),
+            None => self.back_aggregate.clone(),
+        }
+    }
+}
+
+pub trait ToF64 {
+    fn to_f64(self) -> f64;
+}
+
+macro_rules! impl_to_f64 {
+    ($($t:ty),*) => {
+        $(
+            impl ToF64 for $t {
+                fn to_f64(self) -> f64 {
+                    self as f64
+                }
+            }
+        )*
+    };
+}
+
+impl_to_f64!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64);
+
+impl<T: Copy + Default + Add<Output = T> + ToF64> SlidingWindow<Sum<T>> {
+    /// Mean of the window, or `None` if it is empty.
+    #[allow(dead_code)]
+    pub fn average(&self) -> Option<f64> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(self.aggregate().to_f64() / self.len() as f64)
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Extreme {
+    Min,
+    Max,
+}
+
+/// Window min or max in O(1) amortised per push, without needing the two-stack machinery.
+pub struct MonotonicWindow<T: Copy + Ord> {
+    size: usize,
+    extreme: Extreme,
+    pushed: usize,
+    // (index, value) pairs still able to become the extreme, best at the front.
+    candidates: VecDeque<(usize, T)>,
+}
+
+impl<T: Copy + Ord> MonotonicWindow<T> {
+    pub fn new(size: usize, extreme: Extreme) -> Self {
+        assert!(size > 0, "Window size must be positive");
+        MonotonicWindow {
+            size,
+            extreme,
+            pushed: 0,
+            candidates: VecDeque::new(),
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn len(&self) -> usize {
+        self.pushed.min(self.size)
+    }
+
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.pushed == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.pushed >= self.size
+    }
+
+    pub fn push(&mut self, item: T) {
+        // Anything no better than the new item can never be the extreme again.
+        while let Some(&(_, back)) = self.candidates.back() {
+            let dominated = match self.extreme {
+                Extreme::Min => back >= item,
+                Extreme::Max => back <= item,
+            };
+            if !dominated {
+                break;
+            }
+            self.candidates.pop_back();
+        }
+
+        self.candidates.push_back((self.pushed, item));
+        self.pushed += 1;
+
+        while let Some(&(idx, _)) = self.candidates.front() {
+            if idx + self.size >= self.pushed {
+                break;
+            }
+            self.candidates.pop_front();
+        }
+    }
+
+    pub fn extreme(&self) -> Option<T> {
+        self.candidates.front().map(|&(_, val)| val)
+    }
+}
+
+/// Iterator adapters yielding the aggregate of every full window, like `slice::windows`.
+pub trait WindowExt: Iterator + Sized {
+    fn window_aggregate<M: Monoid<Item = Self::Item>>(self, size: usize) -> Windowed<Self, M> {
+        Windowed {
+            iter: self,
+            window: SlidingWindow::new(size),
+        }
+    }
+
+    #[allow(dead_code)]
+    fn window_min(self, size: usize) -> MonotonicWindowed<Self>
+    where
+        Self::Item: Copy + Ord,
+    {
+        MonotonicWindowed {
+            iter: self,
+            window: MonotonicWindow::new(size, Extreme::Min),
+        }
+    }
+
+    #[allow(dead_code)]
+    fn window_max(self, size: usize) -> MonotonicWindowed<Self>
+    where
+        Self::Item: Copy + Ord,
+    {
+        MonotonicWindowed {
+            iter: self,
+            window: MonotonicWindow::new(size, Extreme::Max),
+        }
+    }
+}
+
+impl<I: Iterator> WindowExt for I {}
+
+pub struct Windowed<I: Iterator, M: Monoid<Item = I::Item>> {
+    iter: I,
+    window: SlidingWindow<M>,
+}
+
+impl<I: Iterator, M: Monoid<Item = I::Item>> Iterator for Windowed<I, M> {
+    type Item = M::Item;
+
+    fn next(&mut self) -> Option<M::Item> {
+        loop {
+            self.window.push(self.iter.next()?);
+            if self.window.is_full() {
+                return Some(self.window.aggregate());
+            }
+        }
+    }
+}
+
+pub struct MonotonicWindowed<I: Iterator>
+where
+    I::Item: Copy + Ord,
+{
+    iter: I,
+    window: MonotonicWindow<I::Item>,
+}
+
+impl<I: Iterator> Iterator for MonotonicWindowed<I>
+where
+    I::Item: Copy + Ord,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        loop {
+            self.window.push(self.iter.next()?);
+            if self.window.is_full() {
+                return self.window.extreme();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::random::XorShift;
+
+    // Deterministic pseudo-random values in -50..50.
+    fn values(len: usize) -> Vec<i64> {
+        let mut rng = XorShift::new(0x2545_f491_4f6c_dd1d);
+        (0..len).map(|_| rng.below(100) as i64 - 50).collect()
+    }
+
+    #[test]
+    fn aggregates_match_slice_windows() {
+        let vals = values(200);
+        for size in [1, 2, 3, 7, 50, 200] {
+            let sums = vals
+                .iter()
+                .copied()
+                .window_aggregate::<Sum<i64>>(size)
+                .collect::<Vec<i64>>();
+            let expected = vals
+                .windows(size)
+                .map(|w| w.iter().sum())
+                .collect::<Vec<i64>>();
+            assert_eq!(sums, expected, "sum, size {}", size);
+
+            let mins = vals.iter().copied().window_min(size).collect::<Vec<i64>>();
+            let expected = vals
+                .windows(size)
+                .map(|w| *w.iter().min().unwrap())
+                .collect::<Vec<i64>>();
+            assert_eq!(mins, expected, "min, size {}", size);
+
+            let maxes = vals.iter().copied().window_max(size).collect::<Vec<i64>>();
+            let expected = vals
+                .windows(size)
+                .map(|w| *w.iter().max().unwrap())
+                .collect::<Vec<i64>>();
+            assert_eq!(maxes, expected, "max, size {}", size);
+        }
+    }
+
+    #[test]
+    fn two_stack_min_matches_slice_windows() {
+        let vals = values(100);
+        let mins = vals
+            .iter()
+            .map(|&val| Some(val))
+            .window_aggregate::<Min<i64>>(5)
+            .collect::<Vec<Option<i64>>>();
+        let expected = vals
+            .windows(5)
+            .map(|w| w.iter().min().copied())
+            .collect::<Vec<Option<i64>>>();
+        assert_eq!(mins, expected);
+    }
+
+    #[test]
+    fn average_matches_slice_windows() {
+        let vals = values(100);
+        let mut window: SlidingWindow<Sum<i64>> = SlidingWindow::new(4);
+        assert_eq!(window.average(), None);
+
+        for (idx, &val) in vals.iter().enumerate() {
+            window.push(val);
+            let start = (idx + 1).saturating_sub(4);
+            let expected = vals[start..=idx].iter().sum::<i64>() as f64 / (idx + 1 - start) as f64;
+            assert_eq!(window.average(), Some(expected));
+        }
+    }
+
+    #[test]
+    fn push_evicts_oldest() {
+        let mut window: SlidingWindow<Sum<i64>> = SlidingWindow::new(2);
+        assert_eq!(window.push(1), None);
+        assert_eq!(window.push(2), None);
+        assert_eq!(window.push(3), Some(1));
+        assert_eq!(window.push(4), Some(2));
+        assert_eq!(window.aggregate(), 7);
+    }
+}